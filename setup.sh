@@ -1,2 +1,7 @@
 brew install opencv cmake llvm qt tesseract
-export LIBCLANG_PATH=$(brew --prefix llvm)/lib
\ No newline at end of file
+export LIBCLANG_PATH=$(brew --prefix llvm)/lib
+# Profile face cascade (used by --profile)
+curl -fsSL -o haarcascade_profileface.xml https://raw.githubusercontent.com/opencv/opencv/4.x/data/haarcascades/haarcascade_profileface.xml
+
+# Eye cascade (used by --verify-eyes)
+curl -fsSL -o haarcascade_eye.xml https://raw.githubusercontent.com/opencv/opencv/4.x/data/haarcascades/haarcascade_eye.xml
@@ -0,0 +1,346 @@
+use crate::runlog;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Tokens understood by [`render_name`]
+pub const RENAME_TOKENS: &str = "{name} crop name · {source} source name · {n} number · {status}";
+
+/// Copies `files` into `target`, leaving files that already exist there
+/// untouched. Returns the number copied and the number skipped.
+pub fn copy_files(files: &[PathBuf], target: &Path) -> io::Result<(usize, usize)> {
+    fs::create_dir_all(target)?;
+    let (mut copied, mut skipped) = (0, 0);
+    for file in files {
+        let destination = target.join(file.file_name().unwrap_or_default());
+        if destination.exists() {
+            skipped += 1;
+            continue;
+        }
+        fs::copy(file, destination)?;
+        copied += 1;
+    }
+    Ok((copied, skipped))
+}
+
+/// Writes `files` to a zip archive at `target`, named by their path
+/// relative to `base`. Crops are already compressed images, so entries are
+/// stored rather than deflated. Returns the number of files written. A
+/// partly written archive is removed if writing fails.
+pub fn write_zip(files: &[PathBuf], base: &Path, target: &Path) -> io::Result<usize> {
+    if files.len() > usize::from(u16::MAX) {
+        return Err(too_large("too many files for a zip archive"));
+    }
+
+    let result = File::create(target).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        let written = write_zip_entries(files, base, &mut out)?;
+        out.flush()?;
+        Ok(written)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(target);
+    }
+    result
+}
+
+fn write_zip_entries(files: &[PathBuf], base: &Path, out: &mut impl Write) -> io::Result<usize> {
+    let mut central = Vec::new();
+    let mut offset: u64 = 0;
+    let mut names = HashSet::new();
+
+    for file in files {
+        let data = fs::read(file)?;
+        let name = archive_name(file, base);
+        if !names.insert(name.clone()) {
+            continue; // Same relative path selected twice
+        }
+        let (time, date) = dos_time(fs::metadata(file)?.modified().ok());
+        let crc = crc32fast::hash(&data);
+        let size = u32::try_from(data.len()).map_err(|_| too_large("file over 4 GiB"))?;
+        let local_offset = u32::try_from(offset).map_err(|_| too_large("archive over 4 GiB"))?;
+
+        let mut header = Vec::new();
+        put_u32(&mut header, 0x0403_4b50);
+        put_entry_fields(&mut header, time, date, crc, size, &name);
+        put_u16(&mut header, 0); // Extra field length
+        header.extend_from_slice(name.as_bytes());
+        out.write_all(&header)?;
+        out.write_all(&data)?;
+        offset += (header.len() + data.len()) as u64;
+
+        put_u32(&mut central, 0x0201_4b50);
+        put_u16(&mut central, 20); // Version made by
+        put_entry_fields(&mut central, time, date, crc, size, &name);
+        put_u16(&mut central, 0); // Extra field length
+        put_u16(&mut central, 0); // Comment length
+        put_u16(&mut central, 0); // Disk number
+        put_u16(&mut central, 0); // Internal attributes
+        put_u32(&mut central, 0); // External attributes
+        put_u32(&mut central, local_offset);
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = u32::try_from(offset).map_err(|_| too_large("archive over 4 GiB"))?;
+    let mut end = Vec::new();
+    put_u32(&mut end, 0x0605_4b50);
+    put_u16(&mut end, 0); // This disk
+    put_u16(&mut end, 0); // Disk with the central directory
+    put_u16(&mut end, names.len() as u16);
+    put_u16(&mut end, names.len() as u16);
+    put_u32(&mut end, central.len() as u32);
+    put_u32(&mut end, central_offset);
+    put_u16(&mut end, 0); // Comment length
+
+    out.write_all(&central)?;
+    out.write_all(&end)?;
+    Ok(names.len())
+}
+
+/// Fields shared by local and central headers, from "version needed"
+/// through the file name length.
+fn put_entry_fields(buf: &mut Vec<u8>, time: u16, date: u16, crc: u32, size: u32, name: &str) {
+    put_u16(buf, 20); // Version needed to extract
+    put_u16(buf, 0x0800); // File name is UTF-8
+    put_u16(buf, 0); // Stored
+    put_u16(buf, time);
+    put_u16(buf, date);
+    put_u32(buf, crc);
+    put_u32(buf, size); // Compressed size
+    put_u32(buf, size); // Uncompressed size
+    put_u16(buf, name.len() as u16);
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn too_large(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// `file` relative to `base` with `/` separators, or its file name if it is
+/// elsewhere.
+fn archive_name(file: &Path, base: &Path) -> String {
+    match file.strip_prefix(base) {
+        Ok(relative) => relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => file
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+    }
+}
+
+/// MS-DOS `(time, date)` of a modification time, in UTC. Zip cannot store
+/// dates before 1980, so those become 1980-01-01.
+fn dos_time(modified: Option<SystemTime>) -> (u16, u16) {
+    let secs = modified
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (year, month, day) = runlog::civil_date((secs / 86_400) as i64);
+    if !(1980..=2107).contains(&year) {
+        return (0, (1 << 5) | 1);
+    }
+
+    let secs_of_day = secs % 86_400;
+    let time =
+        ((secs_of_day / 3600) << 11) | ((secs_of_day / 60 % 60) << 5) | (secs_of_day % 60 / 2);
+    let date = ((year - 1980) << 9) | (month << 5) | day;
+    (time as u16, date as u16)
+}
+
+/// Values substituted into a rename template for one crop.
+pub struct RenameFields<'a> {
+    /// Crop file name without extension
+    pub name: &'a str,
+    /// Source file name without extension, if the manifest knows it
+    pub source: Option<&'a str>,
+    /// Position in the selection, starting at 1
+    pub n: usize,
+    pub status: &'a str,
+}
+
+/// Expands `template` for one crop, e.g. `{source}_{n}` to `IMG_0042_3`,
+/// and appends `ext`. `{n}` is zero-padded to `width` digits so the new
+/// names sort in selection order.
+pub fn render_name(
+    template: &str,
+    fields: &RenameFields,
+    width: usize,
+    ext: &str,
+) -> Result<String, String> {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err("Unclosed { in template".to_string());
+        };
+        match &rest[start + 1..start + end] {
+            "name" => name.push_str(fields.name),
+            "source" => name.push_str(fields.source.unwrap_or(fields.name)),
+            "n" => name.push_str(&format!("{:0width$}", fields.n, width = width)),
+            "status" => name.push_str(&fields.status.to_lowercase()),
+            token => return Err(format!("Unknown token {{{}}}", token)),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Template gives an empty name".to_string());
+    }
+    // Characters Windows does not allow in file names
+    let invalid = |c: char| {
+        c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+    };
+    if name.starts_with('.') || name.contains(invalid) {
+        return Err(format!("\"{}\" is not a valid file name", name));
+    }
+    Ok(format!("{}.{}", name, ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    /// Empty folder under the system temp folder, unique to `name`
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("headshot-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn zip_has_local_headers_central_directory_and_end_record() {
+        let dir = scratch_dir("zip");
+        fs::create_dir_all(dir.join("rejected")).unwrap();
+        let files = [dir.join("a.png"), dir.join("rejected").join("b.png")];
+        fs::write(&files[0], b"hello").unwrap();
+        fs::write(&files[1], b"world!").unwrap();
+        let target = dir.join("out.zip");
+
+        assert_eq!(write_zip(&files, &dir, &target).unwrap(), 2);
+        let zip = fs::read(&target).unwrap();
+
+        // Local headers, each followed by its name and stored data
+        let mut offset = 0;
+        let mut local_offsets = Vec::new();
+        for (name, data) in [("a.png", &b"hello"[..]), ("rejected/b.png", &b"world!"[..])] {
+            local_offsets.push(offset as u32);
+            assert_eq!(u32_at(&zip, offset), 0x0403_4b50);
+            assert_eq!(u16_at(&zip, offset + 8), 0); // Stored
+            assert_eq!(u32_at(&zip, offset + 14), crc32fast::hash(data));
+            assert_eq!(u32_at(&zip, offset + 18), data.len() as u32);
+            assert_eq!(u32_at(&zip, offset + 22), data.len() as u32);
+            let name_len = u16_at(&zip, offset + 26) as usize;
+            assert_eq!(u16_at(&zip, offset + 28), 0);
+            assert_eq!(&zip[offset + 30..offset + 30 + name_len], name.as_bytes());
+            let data_start = offset + 30 + name_len;
+            assert_eq!(&zip[data_start..data_start + data.len()], data);
+            offset = data_start + data.len();
+        }
+
+        // End of central directory record
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(&zip, end), 0x0605_4b50);
+        assert_eq!(u16_at(&zip, end + 8), 2);
+        assert_eq!(u16_at(&zip, end + 10), 2);
+        assert_eq!(u32_at(&zip, end + 12) as usize, end - offset);
+        assert_eq!(u32_at(&zip, end + 16) as usize, offset);
+
+        // Central directory entries point back at the local headers
+        let mut entry = offset;
+        for (name, local_offset) in ["a.png", "rejected/b.png"].iter().zip(local_offsets) {
+            assert_eq!(u32_at(&zip, entry), 0x0201_4b50);
+            let name_len = u16_at(&zip, entry + 28) as usize;
+            assert_eq!(u32_at(&zip, entry + 42), local_offset);
+            assert_eq!(&zip[entry + 46..entry + 46 + name_len], name.as_bytes());
+            entry += 46 + name_len;
+        }
+        assert_eq!(entry, end);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn fields<'a>(source: Option<&'a str>) -> RenameFields<'a> {
+        RenameFields {
+            name: "IMG_0042_face_1",
+            source,
+            n: 3,
+            status: "Approved",
+        }
+    }
+
+    #[test]
+    fn template_expands_tokens() {
+        let rendered = render_name("{source}_{n}_{status}", &fields(Some("IMG_0042")), 3, "jpg");
+        assert_eq!(rendered.unwrap(), "IMG_0042_003_approved.jpg");
+    }
+
+    #[test]
+    fn template_falls_back_to_crop_name_without_source() {
+        let rendered = render_name("{source}-{n}", &fields(None), 1, "png");
+        assert_eq!(rendered.unwrap(), "IMG_0042_face_1-3.png");
+    }
+
+    #[test]
+    fn template_rejects_unknown_and_unclosed_tokens() {
+        assert!(render_name("{size}", &fields(None), 1, "png").is_err());
+        assert!(render_name("{name", &fields(None), 1, "png").is_err());
+    }
+
+    #[test]
+    fn template_rejects_empty_and_hidden_names() {
+        assert!(render_name("  ", &fields(None), 1, "png").is_err());
+        assert!(render_name(".{name}", &fields(None), 1, "png").is_err());
+    }
+
+    #[test]
+    fn template_rejects_characters_invalid_on_windows() {
+        for template in [
+            "a/b", "a\\b", "a:b", "a*b", "a?b", "a\"b", "a<b", "a>b", "a|b", "a\tb",
+        ] {
+            assert!(
+                render_name(template, &fields(None), 1, "png").is_err(),
+                "{:?} was accepted",
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn failed_zip_is_removed() {
+        let dir = scratch_dir("zip-failed");
+        fs::write(dir.join("a.png"), b"hello").unwrap();
+        let files = [dir.join("a.png"), dir.join("missing.png")];
+        let target = dir.join("out.zip");
+
+        assert!(write_zip(&files, &dir, &target).is_err());
+        assert!(!target.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,78 @@
+use crate::manifest::{self, MANIFEST_FILE, RUNS_FILE};
+use crate::review::{REJECTED_DIR, REVIEW_FILE};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Subfolder of the output folder that cleared files are moved into
+pub const TRASH_DIR: &str = ".headshot-trash";
+
+/// Files in `dir` that Headshot created: every crop recorded in the
+/// manifest, wherever review moved it, plus the manifest, run record and
+/// review log.
+/// Anything else in the folder is left alone.
+pub fn recorded_outputs(dir: &Path) -> Vec<PathBuf> {
+    let mut crops: Vec<String> = manifest::load(dir).into_keys().collect();
+    crops.sort();
+
+    let mut files: Vec<PathBuf> = crops
+        .into_iter()
+        .flat_map(|crop| [dir.join(&crop), dir.join(REJECTED_DIR).join(&crop)])
+        .filter(|path| path.is_file())
+        .collect();
+    files.extend(
+        [MANIFEST_FILE, RUNS_FILE, REVIEW_FILE]
+            .into_iter()
+            .map(|name| dir.join(name))
+            .filter(|path| path.is_file()),
+    );
+    files
+}
+
+/// Number of files directly inside `dir` and its rejected folder that
+/// Headshot did not record, shown so the user knows what is kept.
+pub fn unrecorded_count(dir: &Path, recorded: &[PathBuf]) -> usize {
+    let recorded: HashSet<&PathBuf> = recorded.iter().collect();
+    [dir.to_path_buf(), dir.join(REJECTED_DIR)]
+        .iter()
+        .filter_map(|folder| fs::read_dir(folder).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && !recorded.contains(path))
+        .count()
+}
+
+/// Removes `files` from the output folder `dir`. With `to_trash` they are
+/// moved into a timestamped folder under `.headshot-trash` instead, keeping
+/// their place relative to `dir` so they can be restored by hand. Returns
+/// the number of files removed.
+pub fn clear_outputs(dir: &Path, files: &[PathBuf], to_trash: bool) -> io::Result<usize> {
+    let trash = if to_trash {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Some(dir.join(TRASH_DIR).join(stamp.to_string()))
+    } else {
+        None
+    };
+
+    let mut removed = 0;
+    for file in files {
+        match &trash {
+            Some(trash) => {
+                let relative = file.strip_prefix(dir).unwrap_or(file);
+                let target = trash.join(relative);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(file, target)?;
+            }
+            None => fs::remove_file(file)?,
+        }
+        removed += 1;
+    }
+    Ok(removed)
+}
@@ -0,0 +1,456 @@
+use opencv::core::AlgorithmHint;
+use opencv::{
+    Error, Result,
+    core::{self, Mat, Point2f, Rect, Scalar, Size, Vector},
+    imgproc,
+    objdetect::CascadeClassifier,
+    prelude::*,
+};
+
+pub const FRONTAL_CASCADE: &str = "haarcascade_frontalface_default.xml";
+pub const PROFILE_CASCADE: &str = "haarcascade_profileface.xml";
+pub const EYE_CASCADE: &str = "haarcascade_eye.xml";
+
+/// Overlap (intersection over union) above which two detections from
+/// different passes are treated as the same face.
+const MERGE_IOU_THRESHOLD: f64 = 0.3;
+
+/// A face size limit, either in pixels or relative to the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FaceSize {
+    Pixels(i32),
+    /// Percentage of the image's shorter side
+    Percent(f64),
+}
+
+impl FaceSize {
+    /// The size in pixels for an image, never less than one pixel so that
+    /// a small limit cannot turn into OpenCV's "no limit".
+    pub fn to_pixels(self, image_width: i32, image_height: i32) -> i32 {
+        let px = match self {
+            FaceSize::Pixels(px) => px,
+            FaceSize::Percent(pct) => {
+                let short_side = image_width.min(image_height) as f64;
+                (short_side * pct / 100.0).round() as i32
+            }
+        };
+        px.max(1)
+    }
+}
+
+impl std::str::FromStr for FaceSize {
+    type Err = String;
+
+    /// Parses `120` as pixels and `15%` as a percentage of the short side.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(pct) = s.strip_suffix('%') {
+            match pct.trim().parse::<f64>() {
+                Ok(pct) if pct > 0.0 && pct <= 100.0 => Ok(FaceSize::Percent(pct)),
+                Ok(_) => Err(format!("percentage must be above 0 and at most 100: {}", s)),
+                Err(_) => Err(format!("invalid percentage: {}", s)),
+            }
+        } else {
+            match s.parse::<i32>() {
+                Ok(px) if px > 0 => Ok(FaceSize::Pixels(px)),
+                Ok(_) => Err(format!("pixel size must be positive: {}", s)),
+                Err(_) => Err(format!("invalid pixel size: {}", s)),
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for FaceSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FaceSize::Pixels(px) => write!(f, "{}", px),
+            FaceSize::Percent(pct) => write!(f, "{}%", pct),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetectionOptions {
+    pub min_neighbors: i32,
+    pub min_face_size: FaceSize,
+    /// Upper bound on face size; unlimited when `None`
+    pub max_face_size: Option<FaceSize>,
+    /// Run the profile cascade on the image and on its horizontal mirror
+    pub profile: bool,
+    /// Extra frontal passes on the image rotated by +/- each angle (degrees)
+    pub rotation_angles: Vec<f64>,
+    /// Reject candidates without at least one eye in their upper half
+    pub verify_eyes: bool,
+    /// Reject candidates whose share of skin-toned pixels is below this
+    pub min_skin_ratio: Option<f64>,
+    /// Run detection on a copy whose longest side is at most this many
+    /// pixels; crops are still cut from the full-resolution image
+    pub detect_max_dimension: Option<i32>,
+}
+
+impl Default for DetectionOptions {
+    fn default() -> Self {
+        Self {
+            min_neighbors: 8,
+            min_face_size: FaceSize::Pixels(100),
+            max_face_size: None,
+            profile: false,
+            rotation_angles: Vec::new(),
+            verify_eyes: false,
+            min_skin_ratio: None,
+            detect_max_dimension: None,
+        }
+    }
+}
+
+impl DetectionOptions {
+    /// Checks that the minimum face size is not above the maximum. Sizes in
+    /// different units can only be compared per image, so they pass.
+    pub fn check_face_sizes(&self) -> std::result::Result<(), String> {
+        let too_large = match (self.min_face_size, self.max_face_size) {
+            (FaceSize::Pixels(min), Some(FaceSize::Pixels(max))) => min > max,
+            (FaceSize::Percent(min), Some(FaceSize::Percent(max))) => min > max,
+            _ => false,
+        };
+        if too_large {
+            return Err(format!(
+                "minimum face size {} is larger than the maximum {}",
+                self.min_face_size,
+                self.max_face_size.unwrap()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Cascade arguments resolved for one image at the scale detection runs at.
+struct CascadeParams {
+    min_neighbors: i32,
+    min_size: Size,
+    max_size: Size,
+}
+
+/// Result of running the detector on one image.
+pub struct Detections {
+    /// Candidates that passed verification
+    pub faces: Vec<Rect>,
+    /// Candidates discarded by the verification step
+    pub rejected: Vec<Rect>,
+}
+
+pub struct FaceDetector {
+    frontal: CascadeClassifier,
+    profile: Option<CascadeClassifier>,
+    eyes: Option<CascadeClassifier>,
+    options: DetectionOptions,
+}
+
+impl FaceDetector {
+    pub fn new(options: DetectionOptions) -> Result<Self> {
+        options
+            .check_face_sizes()
+            .map_err(|e| Error::new(core::StsBadArg, e))?;
+        let frontal = load_cascade(FRONTAL_CASCADE)?;
+        let profile = if options.profile {
+            Some(load_cascade(PROFILE_CASCADE)?)
+        } else {
+            None
+        };
+        let eyes = if options.verify_eyes {
+            Some(load_cascade(EYE_CASCADE)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            frontal,
+            profile,
+            eyes,
+            options,
+        })
+    }
+
+    /// Replaces the neighbour and face size limits, e.g. for an image with
+    /// its own overrides. Unlike the other options these need no cascades
+    /// reloaded.
+    pub fn set_limits(&mut self, options: &DetectionOptions) {
+        self.options.min_neighbors = options.min_neighbors;
+        self.options.min_face_size = options.min_face_size;
+        self.options.max_face_size = options.max_face_size;
+    }
+
+    /// Detects faces in a color image and its grayscale copy, then splits the
+    /// candidates into verified faces and rejected false positives.
+    pub fn detect(&mut self, image: &Mat, gray: &Mat) -> Result<Detections> {
+        let scale = self.detection_scale(gray);
+        let candidates = if scale < 1.0 {
+            let mut small = Mat::default();
+            imgproc::resize(
+                gray,
+                &mut small,
+                Size::default(),
+                scale,
+                scale,
+                imgproc::INTER_AREA,
+            )?;
+            let bounds = Rect::new(0, 0, gray.cols(), gray.rows());
+            self.find_candidates(&small, gray, scale)?
+                .into_iter()
+                .map(|r| scale_rect(&r, 1.0 / scale) & bounds)
+                .filter(|r| !r.empty())
+                .collect()
+        } else {
+            self.find_candidates(gray, gray, 1.0)?
+        };
+
+        let mut detections = Detections {
+            faces: Vec::new(),
+            rejected: Vec::new(),
+        };
+        for candidate in candidates {
+            if self.verify(image, gray, &candidate)? {
+                detections.faces.push(candidate);
+            } else {
+                detections.rejected.push(candidate);
+            }
+        }
+
+        Ok(detections)
+    }
+
+    /// Factor to shrink `gray` by so its longest side fits within
+    /// `detect_max_dimension`, or 1.0 when no downscaling is needed.
+    fn detection_scale(&self, gray: &Mat) -> f64 {
+        let longest = gray.cols().max(gray.rows());
+        match self.options.detect_max_dimension {
+            Some(max_dim) if max_dim > 0 && longest > max_dim => max_dim as f64 / longest as f64,
+            _ => 1.0,
+        }
+    }
+
+    /// Runs every enabled pass on a grayscale image and returns the merged
+    /// face rectangles in that image's coordinates. `full` is the original
+    /// image `gray` was scaled from by `scale`, used to resolve size limits.
+    fn find_candidates(&mut self, gray: &Mat, full: &Mat, scale: f64) -> Result<Vec<Rect>> {
+        let params = self.cascade_params(full, scale);
+        let mut faces = run_cascade(&mut self.frontal, gray, &params)?;
+
+        if let Some(profile) = &mut self.profile {
+            // The profile cascade only finds faces turned one way, so run it
+            // again on the mirrored image to catch the other side.
+            let found = run_cascade(profile, gray, &params)?;
+            merge_detections(&mut faces, found);
+
+            let mut flipped = Mat::default();
+            core::flip(gray, &mut flipped, 1)?;
+            let found = run_cascade(profile, &flipped, &params)?
+                .into_iter()
+                .map(|r| Rect::new(gray.cols() - r.x - r.width, r.y, r.width, r.height))
+                .collect();
+            merge_detections(&mut faces, found);
+        }
+
+        for &angle in &self.options.rotation_angles {
+            for angle in [angle, -angle] {
+                let found = detect_rotated(&mut self.frontal, gray, angle, &params)?;
+                merge_detections(&mut faces, found);
+            }
+        }
+
+        Ok(faces)
+    }
+
+    /// Face size limits are given for the full-resolution image, so they are
+    /// resolved against it and then scaled to the detection image.
+    fn cascade_params(&self, full: &Mat, scale: f64) -> CascadeParams {
+        let to_detection_px = |size: FaceSize| {
+            ((size.to_pixels(full.cols(), full.rows()) as f64 * scale).round() as i32).max(1)
+        };
+
+        let min_px = to_detection_px(self.options.min_face_size);
+        // A zero size tells OpenCV there is no upper bound
+        let max_px = self.options.max_face_size.map(to_detection_px).unwrap_or(0);
+
+        CascadeParams {
+            min_neighbors: self.options.min_neighbors,
+            min_size: Size::new(min_px, min_px),
+            max_size: Size::new(max_px, max_px),
+        }
+    }
+
+    fn verify(&mut self, image: &Mat, gray: &Mat, face: &Rect) -> Result<bool> {
+        if let Some(eyes) = &mut self.eyes {
+            if !has_eye(eyes, gray, face)? {
+                return Ok(false);
+            }
+        }
+
+        if let Some(min_ratio) = self.options.min_skin_ratio {
+            if skin_ratio(image, face)? < min_ratio {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+fn has_eye(cascade: &mut CascadeClassifier, gray: &Mat, face: &Rect) -> Result<bool> {
+    // Eyes sit in the upper part of the face box
+    let upper = Rect::new(face.x, face.y, face.width, (face.height * 3) / 5);
+    let roi = Mat::roi(gray, upper)?;
+
+    let min_eye = (face.width / 8).max(1);
+    let mut eyes: Vector<Rect> = Vector::new();
+    cascade.detect_multi_scale(
+        &roi,
+        &mut eyes,
+        1.1,
+        3,
+        0,
+        Size {
+            width: min_eye,
+            height: min_eye,
+        },
+        Size::default(),
+    )?;
+    Ok(!eyes.is_empty())
+}
+
+/// Share of pixels inside `face` that fall in a common YCrCb skin range.
+fn skin_ratio(image: &Mat, face: &Rect) -> Result<f64> {
+    let roi = Mat::roi(image, *face)?;
+
+    let mut ycrcb = Mat::default();
+    imgproc::cvt_color(
+        &roi,
+        &mut ycrcb,
+        imgproc::COLOR_BGR2YCrCb,
+        0,
+        AlgorithmHint::ALGO_HINT_DEFAULT,
+    )?;
+
+    let mut mask = Mat::default();
+    core::in_range(
+        &ycrcb,
+        &Scalar::new(0.0, 133.0, 77.0, 0.0),
+        &Scalar::new(255.0, 173.0, 127.0, 0.0),
+        &mut mask,
+    )?;
+
+    let area = face.area();
+    if area == 0 {
+        return Ok(0.0);
+    }
+    Ok(core::count_non_zero(&mask)? as f64 / area as f64)
+}
+
+fn load_cascade(path: &str) -> Result<CascadeClassifier> {
+    let cascade = CascadeClassifier::new(path)?;
+    if cascade.empty()? {
+        return Err(Error::new(
+            core::StsObjectNotFound,
+            format!("Failed to load cascade classifier {}", path),
+        ));
+    }
+    Ok(cascade)
+}
+
+fn run_cascade(
+    cascade: &mut CascadeClassifier,
+    gray: &Mat,
+    params: &CascadeParams,
+) -> Result<Vec<Rect>> {
+    let mut faces: Vector<Rect> = Vector::new();
+    cascade.detect_multi_scale(
+        gray,
+        &mut faces,
+        1.4,
+        params.min_neighbors,
+        0,
+        params.min_size,
+        params.max_size,
+    )?;
+    Ok(faces.into_iter().collect())
+}
+
+fn detect_rotated(
+    cascade: &mut CascadeClassifier,
+    gray: &Mat,
+    angle: f64,
+    params: &CascadeParams,
+) -> Result<Vec<Rect>> {
+    let center = Point2f::new(gray.cols() as f32 / 2.0, gray.rows() as f32 / 2.0);
+    let matrix = imgproc::get_rotation_matrix_2d(center, angle, 1.0)?;
+
+    let mut rotated = Mat::default();
+    imgproc::warp_affine(
+        gray,
+        &mut rotated,
+        &matrix,
+        gray.size()?,
+        imgproc::INTER_LINEAR,
+        core::BORDER_CONSTANT,
+        Scalar::default(),
+    )?;
+
+    let faces = run_cascade(cascade, &rotated, params)?
+        .into_iter()
+        .map(|r| unrotate_rect(&r, center, angle))
+        .map(|r| r & Rect::new(0, 0, gray.cols(), gray.rows()))
+        .filter(|r| !r.empty())
+        .collect();
+    Ok(faces)
+}
+
+/// Maps a rectangle found in an image rotated by `angle` degrees around
+/// `center` back to the unrotated image. Only the rectangle's center is
+/// rotated back; its size is kept so the box stays upright and face-sized.
+fn unrotate_rect(rect: &Rect, center: Point2f, angle: f64) -> Rect {
+    let (sin, cos) = (-angle).to_radians().sin_cos();
+    let dx = rect.x as f64 + rect.width as f64 / 2.0 - center.x as f64;
+    let dy = rect.y as f64 + rect.height as f64 / 2.0 - center.y as f64;
+
+    // Same convention as getRotationMatrix2D, with the angle negated
+    let mid_x = cos * dx + sin * dy + center.x as f64;
+    let mid_y = -sin * dx + cos * dy + center.y as f64;
+
+    Rect::new(
+        (mid_x - rect.width as f64 / 2.0).round() as i32,
+        (mid_y - rect.height as f64 / 2.0).round() as i32,
+        rect.width,
+        rect.height,
+    )
+}
+
+fn scale_rect(rect: &Rect, factor: f64) -> Rect {
+    Rect::new(
+        (rect.x as f64 * factor).round() as i32,
+        (rect.y as f64 * factor).round() as i32,
+        (rect.width as f64 * factor).round() as i32,
+        (rect.height as f64 * factor).round() as i32,
+    )
+}
+
+/// Adds `found` to `faces`, skipping any rectangle that overlaps a face that
+/// is already known. Earlier passes win, so frontal detections take priority.
+fn merge_detections(faces: &mut Vec<Rect>, found: Vec<Rect>) {
+    for candidate in found {
+        if !faces
+            .iter()
+            .any(|face| iou(face, &candidate) > MERGE_IOU_THRESHOLD)
+        {
+            faces.push(candidate);
+        }
+    }
+}
+
+fn iou(a: &Rect, b: &Rect) -> f64 {
+    let intersection = (*a & *b).area() as f64;
+    let union = (a.area() + b.area()) as f64 - intersection;
+    if union <= 0.0 {
+        0.0
+    } else {
+        intersection / union
+    }
+}
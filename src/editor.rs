@@ -0,0 +1,336 @@
+use crate::preview;
+use crate::processor;
+use crate::widgets;
+use eframe::egui;
+use opencv::{core::Rect, imgcodecs, prelude::*};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+/// Side of the square resize handle drawn on the selected box, in screen px
+const HANDLE_SIZE: f32 = 10.0;
+
+/// Boxes smaller than this (in source px) are discarded when drawn
+const MIN_BOX_SIZE: i32 = 8;
+
+const BOX_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 200, 80);
+const SELECTED_COLOR: egui::Color32 = egui::Color32::from_rgb(225, 45, 0);
+
+enum Drag {
+    /// Moving a box; `grab` is the pointer offset from the box's top-left
+    Move { index: usize, grab: egui::Vec2 },
+    /// Dragging the bottom-right corner of a box
+    Resize { index: usize },
+    /// Drawing a new box from `start`
+    Create { index: usize, start: egui::Pos2 },
+}
+
+/// Display copy and full-resolution size of the source, or why it could
+/// not be loaded.
+type SourceLoad = std::result::Result<(egui::ColorImage, egui::Vec2), String>;
+
+/// Lets the user fix the face boxes of one source image by hand and
+/// re-export its crops with the usual padding.
+pub struct CropEditor {
+    path: PathBuf,
+    texture: Option<egui::TextureHandle>,
+    /// The source being decoded in the background, until it arrives
+    loading: Option<Receiver<SourceLoad>>,
+    /// Size of the source image at full resolution
+    source_size: egui::Vec2,
+    /// Boxes as originally detected, for "Reset"
+    detected: Vec<Rect>,
+    /// Face boxes being edited, in source pixels
+    boxes: Vec<Rect>,
+    selected: Option<usize>,
+    drag: Option<Drag>,
+    status: Option<String>,
+    error: Option<String>,
+}
+
+impl CropEditor {
+    pub fn open(ctx: &egui::Context, path: PathBuf, faces: Vec<Rect>) -> Self {
+        let (tx, rx) = channel();
+        let source = path.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            tx.send(load_source(&source)).unwrap_or_default();
+            ctx.request_repaint();
+        });
+
+        Self {
+            path,
+            texture: None,
+            loading: Some(rx),
+            source_size: egui::Vec2::ZERO,
+            detected: faces.clone(),
+            boxes: faces,
+            selected: None,
+            drag: None,
+            status: None,
+            error: None,
+        }
+    }
+
+    /// Picks up the decoded source once the background load finishes.
+    fn poll_source(&mut self, ctx: &egui::Context) {
+        let Some(result) = self.loading.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.loading = None;
+        match result {
+            Ok((display, source_size)) => {
+                self.source_size = source_size;
+                self.texture =
+                    Some(ctx.load_texture("crop_editor", display, egui::TextureOptions::LINEAR));
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Shows the editor window. Returns `false` once the user closes it.
+    pub fn show(&mut self, ctx: &egui::Context, output_dir: Option<&Path>) -> bool {
+        let mut editor_open = true;
+        self.poll_source(ctx);
+
+        egui::Window::new("Crop Editor")
+            .resizable(true)
+            .default_size([800.0, 650.0])
+            .open(&mut editor_open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        self.path
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string(),
+                    );
+                    ui.separator();
+                    ui.label(format!("{} faces", self.boxes.len()));
+                });
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(self.selected.is_some(), egui::Button::new("🗑 Delete Box"))
+                        .clicked()
+                    {
+                        self.delete_selected();
+                    }
+                    if ui.button("↺ Reset to Detected").clicked() {
+                        self.boxes = self.detected.clone();
+                        self.selected = None;
+                        self.status = None;
+                    }
+
+                    let export = ui.add_enabled(
+                        output_dir.is_some() && self.texture.is_some(),
+                        egui::Button::new("💾 Export Crops"),
+                    );
+                    if export.clicked() {
+                        if let Some(output_dir) = output_dir {
+                            self.export(output_dir);
+                        }
+                    }
+                });
+
+                ui.label("Drag a box to move it, drag its corner to resize, drag on empty space to add a face. Delete removes the selected box.");
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                if let Some(status) = &self.status {
+                    ui.colored_label(egui::Color32::GREEN, status);
+                }
+
+                ui.separator();
+                if self.loading.is_some() {
+                    ui.centered_and_justified(|ui| {
+                        ui.spinner();
+                    });
+                }
+                self.canvas(ui);
+            });
+
+        editor_open
+    }
+
+    fn delete_selected(&mut self) {
+        if let Some(index) = self.selected.take() {
+            if index < self.boxes.len() {
+                self.boxes.remove(index);
+            }
+        }
+    }
+
+    fn export(&mut self, output_dir: &Path) {
+        match processor::export_crops(&self.path, output_dir, &self.boxes) {
+            Ok(count) => {
+                self.status = Some(format!(
+                    "Exported {} crops to {}",
+                    count,
+                    output_dir.display()
+                ));
+                self.error = None;
+            }
+            Err(e) => {
+                self.error = Some(format!("Export failed: {}", e));
+                self.status = None;
+            }
+        }
+    }
+
+    /// Draws the image with its boxes and handles mouse editing.
+    fn canvas(&mut self, ui: &mut egui::Ui) {
+        let Some(tex) = &self.texture else {
+            return;
+        };
+
+        let available = ui.available_size();
+        let to_screen = (available.x / self.source_size.x).min(available.y / self.source_size.y);
+        let (response, painter) =
+            ui.allocate_painter(self.source_size * to_screen, egui::Sense::click_and_drag());
+        let origin = response.rect.min;
+
+        painter.image(
+            tex.id(),
+            response.rect,
+            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+            egui::Color32::WHITE,
+        );
+
+        let to_source = |pos: egui::Pos2| ((pos - origin) / to_screen).to_pos2();
+        let screen_rect = |rect: &Rect| {
+            egui::Rect::from_min_size(
+                origin + egui::vec2(rect.x as f32, rect.y as f32) * to_screen,
+                egui::vec2(rect.width as f32, rect.height as f32) * to_screen,
+            )
+        };
+        let handle_rect = |rect: &Rect| {
+            egui::Rect::from_center_size(
+                screen_rect(rect).max,
+                egui::vec2(HANDLE_SIZE, HANDLE_SIZE),
+            )
+        };
+        let box_at = |boxes: &[Rect], pos: egui::Pos2| {
+            boxes
+                .iter()
+                .rposition(|rect| screen_rect(rect).contains(pos))
+        };
+
+        if response.clicked() {
+            self.selected = response
+                .interact_pointer_pos()
+                .and_then(|pos| box_at(&self.boxes, pos));
+        }
+
+        if response.drag_started() {
+            let press = ui
+                .input(|i| i.pointer.press_origin())
+                .or(response.interact_pointer_pos());
+            if let Some(pos) = press {
+                let on_handle = self
+                    .selected
+                    .filter(|&index| handle_rect(&self.boxes[index]).contains(pos));
+
+                self.drag = if let Some(index) = on_handle {
+                    Some(Drag::Resize { index })
+                } else if let Some(index) = box_at(&self.boxes, pos) {
+                    self.selected = Some(index);
+                    let rect = &self.boxes[index];
+                    let grab = to_source(pos) - egui::pos2(rect.x as f32, rect.y as f32);
+                    Some(Drag::Move { index, grab })
+                } else {
+                    let start = to_source(pos);
+                    self.boxes
+                        .push(Rect::new(start.x as i32, start.y as i32, 0, 0));
+                    let index = self.boxes.len() - 1;
+                    self.selected = Some(index);
+                    Some(Drag::Create { index, start })
+                };
+            }
+        }
+
+        if response.dragged() {
+            if let (Some(drag), Some(pos)) = (&self.drag, response.interact_pointer_pos()) {
+                let pos = to_source(pos);
+                let bounds = self.source_size;
+                match *drag {
+                    Drag::Move { index, grab } => {
+                        let rect = &mut self.boxes[index];
+                        let max_x = (bounds.x as i32 - rect.width).max(0);
+                        let max_y = (bounds.y as i32 - rect.height).max(0);
+                        rect.x = ((pos.x - grab.x) as i32).clamp(0, max_x);
+                        rect.y = ((pos.y - grab.y) as i32).clamp(0, max_y);
+                    }
+                    Drag::Resize { index } => {
+                        let rect = &mut self.boxes[index];
+                        let right = pos.x.clamp(0.0, bounds.x) as i32;
+                        let bottom = pos.y.clamp(0.0, bounds.y) as i32;
+                        rect.width = (right - rect.x).max(MIN_BOX_SIZE);
+                        rect.height = (bottom - rect.y).max(MIN_BOX_SIZE);
+                    }
+                    Drag::Create { index, start } => {
+                        let end = pos.clamp(egui::Pos2::ZERO, bounds.to_pos2());
+                        let min = start.min(end);
+                        let max = start.max(end);
+                        self.boxes[index] = Rect::new(
+                            min.x as i32,
+                            min.y as i32,
+                            (max.x - min.x) as i32,
+                            (max.y - min.y) as i32,
+                        );
+                    }
+                }
+            }
+        }
+
+        if response.drag_released() {
+            if let Some(Drag::Create { index, .. }) = self.drag {
+                let rect = &self.boxes[index];
+                if rect.width < MIN_BOX_SIZE || rect.height < MIN_BOX_SIZE {
+                    self.boxes.remove(index);
+                    self.selected = None;
+                }
+            }
+            self.drag = None;
+            self.status = None;
+        }
+
+        if self.selected.is_some()
+            && !ui.ctx().wants_keyboard_input()
+            && widgets::window_has_keyboard(ui.ctx(), "Crop Editor")
+            && ui.input_mut(|i| {
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Delete)
+                    || i.consume_key(egui::Modifiers::NONE, egui::Key::Backspace)
+            })
+        {
+            self.delete_selected();
+        }
+
+        for (index, rect) in self.boxes.iter().enumerate() {
+            let color = if self.selected == Some(index) {
+                SELECTED_COLOR
+            } else {
+                BOX_COLOR
+            };
+            painter.rect_stroke(screen_rect(rect), 0.0, egui::Stroke::new(2.0, color));
+            if self.selected == Some(index) {
+                painter.rect_filled(handle_rect(rect), 0.0, color);
+            }
+        }
+    }
+}
+
+/// Reads the source at full resolution and prepares its display copy.
+fn load_source(path: &Path) -> SourceLoad {
+    match imgcodecs::imread(&path.to_string_lossy(), imgcodecs::IMREAD_COLOR) {
+        Ok(image) if !image.empty() => {
+            let source_size = egui::vec2(image.cols() as f32, image.rows() as f32);
+            preview::display_image(&image)
+                .map(|display| (display, source_size))
+                .map_err(|e| e.to_string())
+        }
+        _ => Err(format!("Could not read {}", path.display())),
+    }
+}
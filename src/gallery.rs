@@ -1,54 +1,292 @@
+use crate::bulk::{self, RenameFields};
+use crate::cleanup;
+use crate::manifest::{self, ManifestEntry};
+use crate::preview;
+use crate::processor::{self, Framing};
+use crate::review::{REJECTED_DIR, ReviewLog, ReviewStatus};
+use crate::thumbnails::ThumbnailLoader;
+use crate::watcher::{FolderChange, FolderWatcher};
+use crate::widgets;
 use crossbeam::channel::{Receiver, Sender, unbounded};
 use eframe::egui;
 use egui_extras::{Column, TableBuilder};
 use lru::LruCache;
-use std::collections::HashMap;
+use opencv::{imgcodecs, prelude::*};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::thread;
+use std::time::SystemTime;
 
 #[derive(Clone)]
 pub struct PhotoEntry {
     pub path: PathBuf,
-    pub thumb_tex: Option<egui::TextureHandle>,
     pub thumb_size: egui::Vec2,
     pub last_accessed: std::time::Instant,
+    pub status: ReviewStatus,
+    pub modified: Option<SystemTime>,
+    /// Full size of the image, known once its thumbnail has loaded
+    pub dimensions: Option<[u32; 2]>,
 }
 
 impl PhotoEntry {
     pub fn new(path: PathBuf) -> Self {
         Self {
             path,
-            thumb_tex: None,
             thumb_size: egui::Vec2::new(128.0, 128.0),
             last_accessed: std::time::Instant::now(),
+            status: ReviewStatus::Pending,
+            modified: None,
+            dimensions: None,
         }
     }
+
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// Number of full-resolution textures kept alive for the lightbox
+const FULL_RES_CACHE_SIZE: usize = 8;
+
+/// Number of source image textures kept alive for the side-by-side view
+const SOURCE_CACHE_SIZE: usize = 4;
+
+/// Number of thumbnail textures kept alive; enough for a full screen of
+/// thumbnails, while bounding GPU memory for very large folders
+const THUMB_CACHE_SIZE: usize = 1024;
+
+/// A thumbnail texture and the modification time of the image it shows
+type CachedThumbnail = (SystemTime, egui::TextureHandle);
+
+/// A source image scaled for display, with its full-resolution size
+type SourceImage = (egui::TextureHandle, egui::Vec2);
+
+/// A loaded source image sent back from the loader thread, or `None` if it
+/// could not be read
+type SourceLoad = (PathBuf, Option<(egui::ColorImage, egui::Vec2)>);
+
+const CROP_RECT_COLOR: egui::Color32 = egui::Color32::from_rgb(225, 45, 0);
+const FACE_RECT_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 200, 80);
+
+/// Zoom and pan state of the lightbox for the photo at `path`.
+#[derive(Default)]
+struct LightboxView {
+    path: Option<PathBuf>,
+    /// Display pixels per image pixel; `None` fits the image to the window
+    zoom: Option<f32>,
+    pan: egui::Vec2,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SortKey {
+    #[default]
+    Name,
+    Modified,
+    Source,
+    FaceSize,
+}
+
+impl SortKey {
+    const ALL: [SortKey; 4] = [
+        SortKey::Name,
+        SortKey::Modified,
+        SortKey::Source,
+        SortKey::FaceSize,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            SortKey::Name => "Name",
+            SortKey::Modified => "Date modified",
+            SortKey::Source => "Source image",
+            SortKey::FaceSize => "Face size",
+        }
+    }
+}
+
+/// Sort order and filters chosen in the gallery toolbar.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct GalleryFilter {
+    sort: SortKey,
+    descending: bool,
+    /// Case-insensitive substring of the file name
+    search: String,
+    source: Option<PathBuf>,
+    status: Option<ReviewStatus>,
+    /// Minimum width and height of the crop in pixels; 0 disables
+    min_dimension: u32,
+}
+
+fn is_gallery_image(path: &Path) -> bool {
+    if let Some(ext) = path.extension() {
+        let ext = ext.to_string_lossy().to_lowercase();
+        return matches!(
+            ext.as_str(),
+            "jpg" | "jpeg" | "png" | "bmp" | "tiff" | "webp"
+        );
+    }
+    false
+}
+
+/// An operation on every photo in the multi-selection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BulkAction {
+    Copy,
+    Zip,
+    Reject,
+    Delete,
+    Recrop(Framing),
+    Rename,
+}
+
+/// A re-cropped photo from the worker thread, with its new manifest entry
+type RecropResult = (PathBuf, Result<ManifestEntry, String>);
+
+/// A bulk re-crop running in the background.
+struct RecropJob {
+    dir: PathBuf,
+    receiver: Receiver<RecropResult>,
+    framing: Framing,
+    total: usize,
+    recropped: usize,
+    /// Photos not in the manifest, which were skipped
+    unknown: usize,
+    /// First failure, with the number of photos that failed
+    failed: Option<(String, usize)>,
+}
+
+fn status_color(status: ReviewStatus) -> Option<egui::Color32> {
+    match status {
+        ReviewStatus::Pending => None,
+        ReviewStatus::Approved => Some(egui::Color32::from_rgb(0, 200, 80)),
+        ReviewStatus::Rejected => Some(egui::Color32::from_rgb(225, 45, 0)),
+        ReviewStatus::Flagged => Some(egui::Color32::from_rgb(240, 180, 0)),
+    }
+}
+
+/// Review decision for the key pressed this frame, if any, when the window
+/// titled `window` has the keyboard: A approves, R or Delete rejects,
+/// F flags and U resets to pending. The key is consumed.
+fn review_shortcut(ctx: &egui::Context, window: &str) -> Option<ReviewStatus> {
+    if ctx.wants_keyboard_input() || !widgets::window_has_keyboard(ctx, window) {
+        return None;
+    }
+    ctx.input_mut(|i| {
+        let none = egui::Modifiers::NONE;
+        if i.consume_key(none, egui::Key::A) {
+            Some(ReviewStatus::Approved)
+        } else if i.consume_key(none, egui::Key::R) || i.consume_key(none, egui::Key::Delete) {
+            Some(ReviewStatus::Rejected)
+        } else if i.consume_key(none, egui::Key::F) {
+            Some(ReviewStatus::Flagged)
+        } else if i.consume_key(none, egui::Key::U) {
+            Some(ReviewStatus::Pending)
+        } else {
+            None
+        }
+    })
 }
 
 pub struct Gallery {
+    /// Output folder being shown
+    dir: Option<PathBuf>,
+    /// Picks up crops added, changed or removed outside the gallery
+    watcher: Option<FolderWatcher>,
     photos: Vec<PhotoEntry>,
     photo_map: HashMap<PathBuf, usize>,
-    thumb_receiver: Option<Receiver<(PathBuf, egui::ColorImage)>>,
-    thumb_sender: Option<Sender<(PathBuf, egui::ColorImage)>>,
+    thumbnails: ThumbnailLoader,
+    /// Thumbnail textures of recently shown photos; evicting one frees its
+    /// GPU memory and it is reloaded (from the disk cache) when needed again
+    thumb_cache: LruCache<PathBuf, CachedThumbnail>,
+    /// Images whose thumbnail could not be generated
+    thumb_failed: HashSet<PathBuf>,
+    /// Full-resolution lightbox textures; evicting one frees its GPU memory
     texture_cache: LruCache<PathBuf, egui::TextureHandle>,
-    is_loading: bool,
+    full_receiver: Receiver<(PathBuf, Option<egui::ColorImage>)>,
+    full_sender: Sender<(PathBuf, Option<egui::ColorImage>)>,
+    full_pending: HashSet<PathBuf>,
+    /// Images whose full resolution could not be loaded, until retried
+    full_failed: HashSet<PathBuf>,
+    lightbox_view: LightboxView,
+    /// Crop file name to source image and rectangles, from the manifest
+    manifest: HashMap<String, ManifestEntry>,
+    source_cache: LruCache<PathBuf, SourceImage>,
+    source_receiver: Receiver<SourceLoad>,
+    source_sender: Sender<SourceLoad>,
+    source_pending: HashSet<PathBuf>,
+    source_missing: HashSet<PathBuf>,
+    show_source: bool,
     selected_photo: Option<usize>,
     show_lightbox: bool,
+    filter: GalleryFilter,
+    /// The filter `visible` was last built with; `None` forces a rebuild
+    applied_filter: Option<GalleryFilter>,
+    /// Indices into `photos` that pass the filter, in display order
+    visible: Vec<usize>,
+    /// Distinct source images in the manifest, for the source filter
+    sources: Vec<PathBuf>,
+    /// Thumbnails per grid row in the last frame, for arrow-key navigation
+    grid_cols: usize,
+    /// Set when the keyboard moved the selection out of view
+    scroll_to_selected: bool,
+    hovered_photo: Option<usize>,
+    /// Photos picked with Ctrl- and Shift-click for bulk actions
+    multi_selection: BTreeSet<usize>,
+    /// Last photo clicked without Shift, where Shift-click ranges start
+    selection_anchor: Option<usize>,
+    rename_template: String,
+    review: Option<ReviewLog>,
+    status_message: Option<String>,
+    error_message: Option<String>,
+    recrop: Option<RecropJob>,
 }
 
 impl Gallery {
     pub fn new() -> Self {
-        let (tx, rx) = unbounded();
+        let (full_tx, full_rx) = unbounded();
+        let (source_tx, source_rx) = unbounded();
         Self {
+            dir: None,
+            watcher: None,
             photos: Vec::new(),
             photo_map: HashMap::new(),
-            thumb_receiver: Some(rx),
-            thumb_sender: Some(tx),
-            texture_cache: LruCache::new(NonZeroUsize::new(100).unwrap()),
-            is_loading: false,
+            thumbnails: ThumbnailLoader::new(),
+            thumb_cache: LruCache::new(NonZeroUsize::new(THUMB_CACHE_SIZE).unwrap()),
+            thumb_failed: HashSet::new(),
+            texture_cache: LruCache::new(NonZeroUsize::new(FULL_RES_CACHE_SIZE).unwrap()),
+            full_receiver: full_rx,
+            full_sender: full_tx,
+            full_pending: HashSet::new(),
+            full_failed: HashSet::new(),
+            lightbox_view: LightboxView::default(),
+            manifest: HashMap::new(),
+            source_cache: LruCache::new(NonZeroUsize::new(SOURCE_CACHE_SIZE).unwrap()),
+            source_receiver: source_rx,
+            source_sender: source_tx,
+            source_pending: HashSet::new(),
+            source_missing: HashSet::new(),
+            show_source: true,
             selected_photo: None,
             show_lightbox: false,
+            filter: GalleryFilter::default(),
+            applied_filter: None,
+            visible: Vec::new(),
+            sources: Vec::new(),
+            grid_cols: 1,
+            scroll_to_selected: false,
+            hovered_photo: None,
+            multi_selection: BTreeSet::new(),
+            selection_anchor: None,
+            rename_template: "{source}_{n}".to_string(),
+            review: None,
+            status_message: None,
+            error_message: None,
+            recrop: None,
         }
     }
 
@@ -59,97 +297,326 @@ impl Gallery {
         self.photos.clear();
         self.photo_map.clear();
         self.texture_cache.clear();
-        self.is_loading = true;
-
-        // Collect image files
-        if let Ok(entries) = std::fs::read_dir(&dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if let Some(ext) = path.extension() {
-                    let ext = ext.to_string_lossy().to_lowercase();
-                    if matches!(
-                        ext.as_str(),
-                        "jpg" | "jpeg" | "png" | "bmp" | "tiff" | "webp"
-                    ) {
-                        let photo = PhotoEntry::new(path.clone());
-                        self.photo_map.insert(path.clone(), self.photos.len());
-                        self.photos.push(photo);
+        self.full_failed.clear();
+        self.thumbnails.cancel_pending();
+        self.thumb_failed.clear();
+        self.selected_photo = None;
+        self.show_lightbox = false;
+        self.multi_selection.clear();
+        self.selection_anchor = None;
+        self.status_message = None;
+        self.error_message = None;
+
+        let review = ReviewLog::load(&dir);
+        self.source_missing.clear();
+        self.reload_manifest(&dir);
+
+        // Collect image files, including crops already moved to rejected/
+        for (folder, in_rejected) in [(dir.clone(), false), (review.rejected_dir(), true)] {
+            if let Ok(entries) = std::fs::read_dir(&folder) {
+                for entry in entries.flatten() {
+                    let path = entry.path();
+                    if is_gallery_image(&path) {
+                        let modified = entry.metadata().and_then(|m| m.modified()).ok();
+                        self.insert_photo(&review, path, modified, in_rejected);
                     }
                 }
             }
         }
+        self.review = Some(review);
+        self.dir = Some(dir);
+    }
 
-        // Start background thumbnail generation
-        if let Some(tx) = self.thumb_sender.clone() {
-            let photos_paths: Vec<PathBuf> = self.photos.iter().map(|p| p.path.clone()).collect();
+    /// Shows only the crops of `source`, clearing the other filters so none
+    /// of them are hidden.
+    pub fn show_crops_of(&mut self, source: &Path) {
+        self.filter = GalleryFilter {
+            sort: self.filter.sort,
+            descending: self.filter.descending,
+            source: Some(source.to_path_buf()),
+            ..GalleryFilter::default()
+        };
+    }
 
-            thread::spawn(move || {
-                for path in photos_paths {
-                    if let Ok(img) = image::open(&path) {
-                        // Create thumbnail (max 128px on the longest side)
-                        let thumb = img.thumbnail(128, 128);
-                        let rgba = thumb.to_rgba8();
+    /// Output folder being shown, if any.
+    pub fn directory(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
 
-                        let color_img = egui::ColorImage::from_rgba_unmultiplied(
-                            [rgba.width() as usize, rgba.height() as usize],
-                            rgba.as_flat_samples().as_slice(),
-                        );
+    /// Re-reads the manifest and the list of sources it mentions.
+    fn reload_manifest(&mut self, dir: &Path) {
+        self.manifest = manifest::load(dir);
+        self.applied_filter = None;
 
-                        if tx.send((path, color_img)).is_err() {
-                            break; // Channel closed
-                        }
+        let mut sources: Vec<PathBuf> = self.manifest.values().map(|e| e.source.clone()).collect();
+        sources.sort();
+        sources.dedup();
+        if self
+            .filter
+            .source
+            .as_ref()
+            .is_some_and(|s| !sources.contains(s))
+        {
+            self.filter.source = None;
+        }
+        self.sources = sources;
+    }
+
+    fn insert_photo(
+        &mut self,
+        review: &ReviewLog,
+        path: PathBuf,
+        modified: Option<SystemTime>,
+        in_rejected: bool,
+    ) {
+        let mut photo = PhotoEntry::new(path.clone());
+        photo.modified = modified;
+        photo.status = if in_rejected {
+            ReviewStatus::Rejected
+        } else {
+            match review.get(&photo.file_name()) {
+                // Restored by hand from rejected/, so review it again
+                ReviewStatus::Rejected => ReviewStatus::Pending,
+                status => status,
+            }
+        };
+        self.photo_map.insert(path, self.photos.len());
+        self.photos.push(photo);
+        self.applied_filter = None;
+    }
+
+    /// Adds crops a run has just written, with their manifest entries, so
+    /// the manifest does not have to be read again. Crops written to
+    /// another folder are ignored.
+    pub fn add_crops(&mut self, crops: &[PathBuf], entries: &[ManifestEntry]) {
+        let Some(dir) = self.dir.clone() else {
+            return;
+        };
+        for (path, entry) in crops.iter().zip(entries) {
+            if path.parent() != Some(dir.as_path()) {
+                continue;
+            }
+            if let Err(pos) = self.sources.binary_search(&entry.source) {
+                self.sources.insert(pos, entry.source.clone());
+            }
+            self.manifest.insert(entry.crop.clone(), entry.clone());
+            self.applied_filter = None;
+        }
+        self.insert_new_photos(&dir, crops);
+    }
+
+    /// Adds crops that appeared in the shown folder while it is open.
+    /// Paths already shown or elsewhere are ignored.
+    fn add_photos(&mut self, paths: &[PathBuf]) {
+        let Some(dir) = self.dir.clone() else {
+            return;
+        };
+        // Crops from elsewhere, e.g. a command-line run, may be in the manifest
+        if self.insert_new_photos(&dir, paths) {
+            self.reload_manifest(&dir);
+        }
+    }
+
+    /// Adds the photos among `paths` that are new files in `dir` or its
+    /// rejected folder. Returns whether any were added.
+    fn insert_new_photos(&mut self, dir: &Path, paths: &[PathBuf]) -> bool {
+        let Some(review) = self.review.take() else {
+            return false;
+        };
+
+        let rejected_dir = review.rejected_dir();
+        let mut added = false;
+        for path in paths {
+            let in_rejected = path.parent() == Some(rejected_dir.as_path());
+            let in_dir = path.parent() == Some(dir);
+            if !(in_dir || in_rejected)
+                || self.photo_map.contains_key(path)
+                || !is_gallery_image(path)
+                || !path.is_file()
+            {
+                continue;
+            }
+            let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+            self.insert_photo(&review, path.clone(), modified, in_rejected);
+            added = true;
+        }
+        self.review = Some(review);
+        added
+    }
+
+    /// Drops photos whose files no longer exist.
+    fn remove_photos(&mut self, paths: &HashSet<PathBuf>) {
+        let path_of = |index: usize| self.photos.get(index).map(|photo| photo.path.clone());
+        let selected = self.selected_photo.and_then(path_of);
+        let anchor = self.selection_anchor.and_then(path_of);
+        let marked: Vec<PathBuf> = self
+            .multi_selection
+            .iter()
+            .filter_map(|&index| path_of(index))
+            .collect();
+
+        self.photos.retain(|photo| !paths.contains(&photo.path));
+        self.photo_map = self
+            .photos
+            .iter()
+            .enumerate()
+            .map(|(index, photo)| (photo.path.clone(), index))
+            .collect();
+        for path in paths {
+            self.texture_cache.pop(path);
+            self.full_failed.remove(path);
+            self.thumb_cache.pop(path);
+            self.thumb_failed.remove(path);
+        }
+
+        self.selected_photo = selected.and_then(|path| self.photo_map.get(&path).copied());
+        if self.selected_photo.is_none() {
+            self.show_lightbox = false;
+        }
+        self.selection_anchor = anchor.and_then(|path| self.photo_map.get(&path).copied());
+        self.multi_selection = marked
+            .iter()
+            .filter_map(|path| self.photo_map.get(path).copied())
+            .collect();
+        self.hovered_photo = None;
+        self.applied_filter = None;
+    }
+
+    /// Applies changes the folder watcher found since the last frame.
+    fn apply_folder_changes(&mut self, ctx: &egui::Context) {
+        let Some(dir) = self.dir.clone() else {
+            self.watcher = None;
+            return;
+        };
+        let folders = vec![dir.join(REJECTED_DIR), dir];
+        if self.watcher.as_ref().map(|w| w.folders()) != Some(folders.as_slice()) {
+            // Seeded from the listing so files created since are reported
+            let known = self
+                .photos
+                .iter()
+                .map(|photo| (photo.path.clone(), photo.modified))
+                .collect();
+            self.watcher = Some(FolderWatcher::new(folders, known, ctx));
+            return;
+        }
+        let Some(watcher) = &self.watcher else {
+            return;
+        };
+
+        let mut added = Vec::new();
+        let mut removed = HashSet::new();
+        for change in watcher.changes() {
+            match change {
+                FolderChange::Added(path, _) => {
+                    removed.remove(&path);
+                    added.push(path);
+                }
+                FolderChange::Modified(path, modified) => {
+                    if let Some(&index) = self.photo_map.get(&path) {
+                        // The thumbnail cache compares this against the
+                        // time its texture was made from
+                        self.photos[index].modified = modified;
+                        self.texture_cache.pop(&path);
+                        self.full_failed.remove(&path);
+                        self.thumb_failed.remove(&path);
                     }
                 }
-            });
+                FolderChange::Removed(path) => {
+                    added.retain(|p| p != &path);
+                    removed.insert(path);
+                }
+            }
+        }
+
+        // A review move shows up as a removal and an addition of paths the
+        // gallery has already updated, so only act on what still differs
+        removed.retain(|path| self.photo_map.contains_key(path) && !path.exists());
+        if !removed.is_empty() {
+            self.remove_photos(&removed);
+        }
+        if !added.is_empty() {
+            self.add_photos(&added);
         }
     }
 
     pub fn update(&mut self, ctx: &egui::Context) {
+        self.apply_folder_changes(ctx);
+        self.apply_recrop_results();
+
         // Process incoming thumbnails
-        if let Some(rx) = &self.thumb_receiver {
-            let mut any_received = false;
-            while let Ok((path, color_img)) = rx.try_recv() {
-                if let Some(&index) = self.photo_map.get(&path) {
-                    let tex_name = format!("thumb_{}", path.to_string_lossy());
-                    let texture =
-                        ctx.load_texture(tex_name, color_img, egui::TextureOptions::LINEAR);
+        let mut any_received = false;
+        while let Some((path, thumbnail)) = self.thumbnails.try_recv() {
+            let Some(&index) = self.photo_map.get(&path) else {
+                continue; // From a folder that is no longer shown
+            };
+            let Some(thumbnail) = thumbnail else {
+                self.thumb_failed.insert(path);
+                continue;
+            };
 
-                    // Update photo entry
-                    if let Some(photo) = self.photos.get_mut(index) {
-                        photo.thumb_tex = Some(texture.clone());
-                        photo.thumb_size =
-                            egui::Vec2::new(texture.size()[0] as f32, texture.size()[1] as f32);
-                    }
+            let tex_name = format!("thumb_{}", path.to_string_lossy());
+            let texture = ctx.load_texture(tex_name, thumbnail.image, egui::TextureOptions::LINEAR);
+            if let Some(photo) = self.photos.get_mut(index) {
+                photo.thumb_size = texture.size_vec2();
+                photo.dimensions = Some(thumbnail.dimensions);
+            }
+            self.thumb_cache.put(path, (thumbnail.modified, texture));
+            any_received = true;
+        }
 
-                    // Cache the texture
-                    self.texture_cache.put(path, texture);
-                    any_received = true;
-                }
+        if any_received {
+            // Crops missing from the manifest only get a size now
+            if self.filter.min_dimension > 0 {
+                self.applied_filter = None;
             }
+            ctx.request_repaint();
+        }
 
-            if any_received {
-                ctx.request_repaint();
+        // Process incoming full-resolution images
+        while let Ok((path, loaded)) = self.full_receiver.try_recv() {
+            self.full_pending.remove(&path);
+            match loaded {
+                Some(color_img) => {
+                    let tex_name = format!("full_{}", path.to_string_lossy());
+                    let texture =
+                        ctx.load_texture(tex_name, color_img, egui::TextureOptions::LINEAR);
+                    self.texture_cache.put(path, texture);
+                }
+                None => {
+                    self.full_failed.insert(path);
+                }
             }
+            ctx.request_repaint();
         }
 
-        // Check if all thumbnails are loaded
-        if self.is_loading {
-            let all_loaded = self.photos.iter().all(|p| p.thumb_tex.is_some());
-            if all_loaded {
-                self.is_loading = false;
+        // Process incoming source images
+        while let Ok((path, loaded)) = self.source_receiver.try_recv() {
+            self.source_pending.remove(&path);
+            match loaded {
+                Some((color_img, source_size)) => {
+                    let tex_name = format!("source_{}", path.to_string_lossy());
+                    let texture =
+                        ctx.load_texture(tex_name, color_img, egui::TextureOptions::LINEAR);
+                    self.source_cache.put(path, (texture, source_size));
+                }
+                None => {
+                    self.source_missing.insert(path);
+                }
             }
+            ctx.request_repaint();
         }
     }
 
     pub fn show(&mut self, ctx: &egui::Context) -> bool {
-        let mut gallery_open = true;
+        self.refresh_visible();
+        let mut gallery_open = self.handle_navigation_keys(ctx);
 
         egui::Window::new("Gallery")
             .resizable(true)
             .default_size([800.0, 600.0])
             .open(&mut gallery_open)
             .show(ctx, |ui| {
-                if self.is_loading {
+                if self.is_loading() {
                     ui.horizontal(|ui| {
                         ui.spinner();
                         ui.label("Loading thumbnails...");
@@ -163,34 +630,120 @@ impl Gallery {
                     return;
                 }
 
+                self.filter_toolbar(ui);
+                if !self.multi_selection.is_empty() {
+                    ui.separator();
+                    if let Some(action) = self.selection_toolbar(ui) {
+                        self.run_bulk_action(action);
+                    }
+                }
+                ui.separator();
+
+                self.hovered_photo = None;
+                let mut review_action: Option<(usize, ReviewStatus)> = None;
+                let mut clicked: Option<(usize, egui::Modifiers)> = None;
+
                 // Gallery grid
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     let available_width = ui.available_width();
                     let thumb_size = 140.0;
                     let cols = ((available_width / thumb_size).floor() as usize).max(1);
-                    let rows = (self.photos.len() + cols - 1) / cols;
+                    let rows = (self.visible.len() + cols - 1) / cols;
+                    self.grid_cols = cols;
 
-                    TableBuilder::new(ui)
-                        .columns(Column::exact(thumb_size), cols)
-                        .body(|mut body| {
+                    let mut table =
+                        TableBuilder::new(ui).columns(Column::exact(thumb_size), cols);
+                    if std::mem::take(&mut self.scroll_to_selected) {
+                        if let Some(position) = self.selected_position() {
+                            table = table.scroll_to_row(position / cols, None);
+                        }
+                    }
+                    table.body(|mut body| {
                             body.rows(thumb_size, rows, |mut row| {
                                 let row_index = row.index();
                                 for col in 0..cols {
-                                    let photo_index = row_index * cols + col;
+                                    let Some(&photo_index) =
+                                        self.visible.get(row_index * cols + col)
+                                    else {
+                                        continue;
+                                    };
                                     if let Some(photo) = self.photos.get_mut(photo_index) {
+                                        // Only rows on screen get here, so
+                                        // thumbnails load as they scroll in
+                                        let thumb = self
+                                            .thumb_cache
+                                            .get(&photo.path)
+                                            .filter(|(modified, _)| {
+                                                photo.modified.is_none_or(|m| m == *modified)
+                                            })
+                                            .map(|(_, tex)| tex.clone());
+                                        let failed = self.thumb_failed.contains(&photo.path);
+                                        if thumb.is_none() && !failed {
+                                            self.thumbnails.request(&photo.path);
+                                        }
+
                                         row.col(|ui| {
-                                            if let Some(tex) = &photo.thumb_tex {
+                                            if let Some(tex) = &thumb {
                                                 let response = ui.add_sized(
                                                     [120.0, 120.0],
                                                     egui::ImageButton::new(tex),
                                                 );
 
                                                 if response.clicked() {
-                                                    self.selected_photo = Some(photo_index);
-                                                    self.show_lightbox = true;
+                                                    clicked = Some((
+                                                        photo_index,
+                                                        ui.input(|i| i.modifiers),
+                                                    ));
+                                                }
+
+                                                if let Some(color) = status_color(photo.status) {
+                                                    ui.painter().rect_stroke(
+                                                        response.rect,
+                                                        4.0,
+                                                        egui::Stroke::new(3.0, color),
+                                                    );
+                                                }
+                                                if self.multi_selection.contains(&photo_index) {
+                                                    let selection = ui.visuals().selection;
+                                                    ui.painter().rect_filled(
+                                                        response.rect,
+                                                        4.0,
+                                                        selection.bg_fill.gamma_multiply(0.4),
+                                                    );
+                                                    ui.painter().text(
+                                                        response.rect.right_top()
+                                                            + egui::vec2(-6.0, 6.0),
+                                                        egui::Align2::RIGHT_TOP,
+                                                        "✔",
+                                                        egui::FontId::proportional(18.0),
+                                                        selection.stroke.color,
+                                                    );
+                                                }
+                                                if self.selected_photo == Some(photo_index) {
+                                                    ui.painter().rect_stroke(
+                                                        response.rect.expand(3.0),
+                                                        6.0,
+                                                        ui.visuals().selection.stroke,
+                                                    );
                                                 }
 
+                                                response.context_menu(|ui| {
+                                                    for status in [
+                                                        ReviewStatus::Approved,
+                                                        ReviewStatus::Rejected,
+                                                        ReviewStatus::Flagged,
+                                                        ReviewStatus::Pending,
+                                                    ] {
+                                                        if ui.button(status.label()).clicked() {
+                                                            review_action =
+                                                                Some((photo_index, status));
+                                                            ui.close_menu();
+                                                        }
+                                                    }
+                                                });
+
                                                 if response.hovered() {
+                                                    self.hovered_photo = Some(photo_index);
                                                     response.on_hover_text(
                                                         photo
                                                             .path
@@ -202,6 +755,12 @@ impl Gallery {
                                                 }
 
                                                 photo.last_accessed = std::time::Instant::now();
+                                            } else if failed {
+                                                ui.add_sized(
+                                                    [120.0, 120.0],
+                                                    egui::Label::new("⚠ Unreadable"),
+                                                )
+                                                .on_hover_text(photo.path.display().to_string());
                                             } else {
                                                 // Placeholder while loading
                                                 ui.add_sized([120.0, 120.0], egui::Spinner::new());
@@ -213,9 +772,51 @@ impl Gallery {
                         });
                 });
 
-                // Show image count
+                // Review shortcuts act on the hovered thumbnail, or the
+                // keyboard selection when the mouse is elsewhere
+                if !self.show_lightbox {
+                    if let (Some(index), Some(status)) = (
+                        self.hovered_photo.or(self.selected_photo),
+                        review_shortcut(ctx, "Gallery"),
+                    )
+                    {
+                        review_action = Some((index, status));
+                    }
+                }
+                if let Some((index, status)) = review_action {
+                    self.set_status(index, status);
+                }
+                if let Some((index, modifiers)) = clicked {
+                    self.click_photo(index, modifiers);
+                }
+
+                // Show image count and review progress
                 ui.separator();
-                ui.label(format!("Total images: {}", self.photos.len()));
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "Showing {} of {} images",
+                        self.visible.len(),
+                        self.photos.len()
+                    ));
+                    for status in [
+                        ReviewStatus::Approved,
+                        ReviewStatus::Rejected,
+                        ReviewStatus::Flagged,
+                        ReviewStatus::Pending,
+                    ] {
+                        let count = self.photos.iter().filter(|p| p.status == status).count();
+                        ui.separator();
+                        ui.label(format!("{}: {}", status.label(), count));
+                    }
+                });
+                ui.label("Review: A approve · R/Del reject · F flag · U undo (hover a thumbnail or use the viewer)");
+                ui.label("Arrow keys move the selection · Enter opens it · Esc closes");
+                ui.label("Ctrl-click or Shift-click to select several · Ctrl+A selects all shown");
+                if let Some(error) = &self.error_message {
+                    ui.colored_label(egui::Color32::RED, error);
+                } else if let Some(status) = &self.status_message {
+                    ui.label(status);
+                }
             });
 
         // Show lightbox if selected
@@ -226,8 +827,706 @@ impl Gallery {
         gallery_open
     }
 
+    /// Rebuilds the list of visible photos if the filter or the photos have
+    /// changed since it was last built.
+    fn refresh_visible(&mut self) {
+        if self.applied_filter.as_ref() == Some(&self.filter) {
+            return;
+        }
+
+        let filter = &self.filter;
+        let manifest = &self.manifest;
+        let search = filter.search.to_lowercase();
+        let mut visible: Vec<usize> = (0..self.photos.len())
+            .filter(|&index| {
+                let photo = &self.photos[index];
+                let entry = manifest.get(&photo.file_name());
+                let size = entry
+                    .map(|e| [e.crop_rect.width as u32, e.crop_rect.height as u32])
+                    .or(photo.dimensions);
+
+                (search.is_empty() || photo.file_name().to_lowercase().contains(&search))
+                    && filter.status.is_none_or(|status| photo.status == status)
+                    && filter
+                        .source
+                        .as_ref()
+                        .is_none_or(|source| entry.is_some_and(|e| &e.source == source))
+                    && (filter.min_dimension == 0
+                        || size.is_some_and(|[w, h]| w.min(h) >= filter.min_dimension))
+            })
+            .collect();
+
+        let photos = &self.photos;
+        let face_area = |index: usize| {
+            manifest
+                .get(&photos[index].file_name())
+                .map_or(0, |e| e.face_rect.area())
+        };
+        let source = |index: usize| manifest.get(&photos[index].file_name()).map(|e| &e.source);
+        visible.sort_by_cached_key(|&index| photos[index].file_name().to_lowercase());
+        match filter.sort {
+            SortKey::Name => {}
+            SortKey::Modified => visible.sort_by_key(|&index| photos[index].modified),
+            SortKey::Source => visible.sort_by(|&a, &b| source(a).cmp(&source(b))),
+            SortKey::FaceSize => visible.sort_by_key(|&index| face_area(index)),
+        }
+        if filter.descending {
+            visible.reverse();
+        }
+
+        // Bulk actions only apply to photos that can be seen
+        if !self.multi_selection.is_empty() {
+            let shown: HashSet<usize> = visible.iter().copied().collect();
+            self.multi_selection.retain(|index| shown.contains(index));
+        }
+
+        self.visible = visible;
+        self.applied_filter = Some(self.filter.clone());
+    }
+
+    /// Selects a clicked thumbnail: Ctrl toggles it in the multi-selection,
+    /// Shift adds the range from the last click, and a plain click opens it.
+    fn click_photo(&mut self, index: usize, modifiers: egui::Modifiers) {
+        if modifiers.shift {
+            let position = self.visible.iter().position(|&i| i == index);
+            let anchor = self
+                .selection_anchor
+                .and_then(|anchor| self.visible.iter().position(|&i| i == anchor))
+                .or(position);
+            if let (Some(anchor), Some(position)) = (anchor, position) {
+                if !modifiers.command {
+                    self.multi_selection.clear();
+                }
+                let range = anchor.min(position)..=anchor.max(position);
+                self.multi_selection.extend(&self.visible[range]);
+            }
+        } else if modifiers.command {
+            if !self.multi_selection.remove(&index) {
+                self.multi_selection.insert(index);
+            }
+            self.selection_anchor = Some(index);
+        } else {
+            self.multi_selection.clear();
+            self.selection_anchor = Some(index);
+            self.show_lightbox = true;
+        }
+        self.selected_photo = Some(index);
+    }
+
+    /// The multi-selection in display order.
+    fn selection_in_order(&self) -> Vec<usize> {
+        self.visible
+            .iter()
+            .copied()
+            .filter(|index| self.multi_selection.contains(index))
+            .collect()
+    }
+
+    /// Bulk action buttons, shown while photos are multi-selected.
+    fn selection_toolbar(&mut self, ui: &mut egui::Ui) -> Option<BulkAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.strong(format!("{} selected", self.multi_selection.len()));
+            if ui.button("📁 Copy to Folder...").clicked() {
+                action = Some(BulkAction::Copy);
+            }
+            if ui.button("🗜 Export Zip...").clicked() {
+                action = Some(BulkAction::Zip);
+            }
+            if ui.button("Reject").clicked() {
+                action = Some(BulkAction::Reject);
+            }
+            if ui
+                .button("🗑 Delete")
+                .on_hover_text(format!(
+                    "Move to {} in the output folder",
+                    cleanup::TRASH_DIR
+                ))
+                .clicked()
+            {
+                action = Some(BulkAction::Delete);
+            }
+            ui.menu_button("✂ Re-crop", |ui| {
+                for framing in Framing::ALL {
+                    if ui.button(framing.label()).clicked() {
+                        action = Some(BulkAction::Recrop(framing));
+                        ui.close_menu();
+                    }
+                }
+            })
+            .response
+            .on_hover_text("Crop again from the source with more or less room around the face");
+            if ui.button("Clear Selection").clicked() {
+                self.multi_selection.clear();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Rename to");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.rename_template)
+                    .hint_text("{source}_{n}")
+                    .desired_width(200.0),
+            )
+            .on_hover_text(bulk::RENAME_TOKENS);
+            if ui
+                .add_enabled(
+                    !self.rename_template.trim().is_empty(),
+                    egui::Button::new("Rename"),
+                )
+                .clicked()
+            {
+                action = Some(BulkAction::Rename);
+            }
+        });
+        action
+    }
+
+    fn run_bulk_action(&mut self, action: BulkAction) {
+        let Some(dir) = self.dir.clone() else {
+            return;
+        };
+        let indices = self.selection_in_order();
+        let files: Vec<PathBuf> = indices
+            .iter()
+            .map(|&index| self.photos[index].path.clone())
+            .collect();
+        if self.recrop.is_some() {
+            self.error_message = Some("Wait for the re-crop to finish".to_string());
+            return;
+        }
+        self.status_message = None;
+        self.error_message = None;
+
+        match action {
+            BulkAction::Copy => {
+                let Some(target) = rfd::FileDialog::new()
+                    .set_title("Copy Selected Crops To")
+                    .pick_folder()
+                else {
+                    return;
+                };
+                match bulk::copy_files(&files, &target) {
+                    Ok((copied, skipped)) => {
+                        self.status_message = Some(format!(
+                            "Copied {} crops to {}{}",
+                            copied,
+                            target.display(),
+                            if skipped > 0 {
+                                format!(" ({} already there, skipped)", skipped)
+                            } else {
+                                String::new()
+                            }
+                        ));
+                    }
+                    Err(e) => self.error_message = Some(format!("Could not copy crops: {}", e)),
+                }
+            }
+            BulkAction::Zip => {
+                let Some(target) = rfd::FileDialog::new()
+                    .set_title("Export Selected Crops")
+                    .add_filter("Zip archive", &["zip"])
+                    .set_file_name("headshot-crops.zip")
+                    .save_file()
+                else {
+                    return;
+                };
+                match bulk::write_zip(&files, &dir, &target) {
+                    Ok(count) => {
+                        self.status_message =
+                            Some(format!("Wrote {} crops to {}", count, target.display()));
+                    }
+                    Err(e) => self.error_message = Some(format!("Could not write zip: {}", e)),
+                }
+            }
+            BulkAction::Reject => {
+                for &index in &indices {
+                    self.set_status(index, ReviewStatus::Rejected);
+                }
+                if self.error_message.is_none() {
+                    self.status_message = Some(format!("Rejected {} crops", indices.len()));
+                }
+            }
+            BulkAction::Delete => self.delete_photos(&dir, &files),
+            BulkAction::Recrop(framing) => self.recrop_photos(&dir, &indices, framing),
+            BulkAction::Rename => self.rename_photos(&dir, &indices),
+        }
+    }
+
+    /// Moves the files into the output folder's trash and drops them from
+    /// the gallery and the review log.
+    fn delete_photos(&mut self, dir: &Path, files: &[PathBuf]) {
+        let result = cleanup::clear_outputs(dir, files, true);
+
+        // Files moved before an error are gone as well
+        let removed: HashSet<PathBuf> = files.iter().filter(|f| !f.exists()).cloned().collect();
+        if let Some(review) = &mut self.review {
+            for path in &removed {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                review.set(&name, ReviewStatus::Pending).unwrap_or_default();
+            }
+        }
+        self.remove_photos(&removed);
+
+        match result {
+            Ok(count) => {
+                self.status_message = Some(format!(
+                    "Moved {} crops to {}",
+                    count,
+                    dir.join(cleanup::TRASH_DIR).display()
+                ));
+            }
+            Err(e) => self.error_message = Some(format!("Could not delete crops: {}", e)),
+        }
+    }
+
+    /// Starts cropping the photos again from their sources with a different
+    /// framing. Sources are read at full resolution, so this runs on a
+    /// worker thread and [`Self::apply_recrop_results`] picks up the results.
+    fn recrop_photos(&mut self, dir: &Path, indices: &[usize], framing: Framing) {
+        let mut work = Vec::new();
+        let mut unknown = 0;
+        for &index in indices {
+            let photo = &self.photos[index];
+            match self.manifest.get(&photo.file_name()) {
+                Some(entry) => work.push((photo.path.clone(), entry.clone())),
+                None => unknown += 1, // Not made by Headshot, so there is no face box
+            }
+        }
+
+        let (tx, rx) = unbounded();
+        let total = work.len();
+        let manifest_dir = dir.to_path_buf();
+        thread::spawn(move || {
+            for (path, entry) in work {
+                let result = processor::recrop(&entry, &path, &manifest_dir, framing)
+                    .map_err(|e| e.to_string());
+                if tx.send((path, result)).is_err() {
+                    break; // Gallery dropped
+                }
+            }
+        });
+
+        self.recrop = Some(RecropJob {
+            dir: dir.to_path_buf(),
+            receiver: rx,
+            framing,
+            total,
+            recropped: 0,
+            unknown,
+            failed: None,
+        });
+        self.apply_recrop_results();
+    }
+
+    /// Updates the photos re-cropped since the last frame and reports the
+    /// outcome once every photo is done.
+    fn apply_recrop_results(&mut self) {
+        let Some(job) = &mut self.recrop else {
+            return;
+        };
+        let shown = self.dir.as_ref() == Some(&job.dir);
+        for (path, result) in job.receiver.try_iter() {
+            match result {
+                Ok(entry) => {
+                    job.recropped += 1;
+                    if !shown {
+                        continue;
+                    }
+                    if let Some(&index) = self.photo_map.get(&path) {
+                        self.photos[index].modified =
+                            std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+                    }
+                    self.texture_cache.pop(&path);
+                    self.full_failed.remove(&path);
+                    self.manifest.insert(entry.crop.clone(), entry);
+                    self.applied_filter = None;
+                }
+                Err(e) => {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    let failed = job
+                        .failed
+                        .get_or_insert_with(|| (format!("{}: {}", name, e), 0));
+                    failed.1 += 1;
+                }
+            }
+        }
+
+        let done = job.recropped + job.failed.as_ref().map_or(0, |(_, count)| *count);
+        if done < job.total {
+            self.status_message = Some(format!("Re-cropping {} of {}...", done, job.total));
+            return;
+        }
+
+        self.status_message = Some(format!(
+            "Re-cropped {} crops with {} framing{}",
+            job.recropped,
+            job.framing.label().to_lowercase(),
+            if job.unknown > 0 {
+                format!(" ({} not in the manifest, skipped)", job.unknown)
+            } else {
+                String::new()
+            }
+        ));
+        if let Some((first, count)) = &job.failed {
+            self.error_message = Some(format!(
+                "Could not re-crop {} of {} crops; first error: {}",
+                count, job.total, first
+            ));
+        }
+        self.recrop = None;
+    }
+
+    /// Renames the photos from the rename template, carrying their review
+    /// status and manifest entry over to the new name. Nothing is renamed if
+    /// any new name is invalid or taken.
+    fn rename_photos(&mut self, dir: &Path, indices: &[usize]) {
+        let width = indices.len().to_string().len();
+        let mut renames = Vec::new();
+        let mut targets = HashSet::new();
+        for (n, &index) in indices.iter().enumerate() {
+            let photo = &self.photos[index];
+            let entry = self.manifest.get(&photo.file_name());
+            let source = entry
+                .and_then(|e| e.source.file_stem())
+                .map(|s| s.to_string_lossy());
+            let name = photo.path.file_stem().unwrap_or_default().to_string_lossy();
+            let ext = photo.path.extension().unwrap_or_default().to_string_lossy();
+            let fields = RenameFields {
+                name: &name,
+                source: source.as_deref(),
+                n: n + 1,
+                status: photo.status.label(),
+            };
+
+            let new_name = match bulk::render_name(&self.rename_template, &fields, width, &ext) {
+                Ok(new_name) => new_name,
+                Err(e) => {
+                    self.error_message = Some(e);
+                    return;
+                }
+            };
+            let target = photo.path.with_file_name(&new_name);
+            if target == photo.path {
+                continue;
+            }
+            if target.exists() || !targets.insert(target.clone()) {
+                self.error_message = Some(format!(
+                    "Renaming would overwrite {}; add {{n}} to the template",
+                    new_name
+                ));
+                return;
+            }
+            renames.push((index, target));
+        }
+
+        let mut renamed = 0;
+        for (index, target) in renames {
+            let photo = &mut self.photos[index];
+            if let Err(e) = std::fs::rename(&photo.path, &target) {
+                self.error_message = Some(format!("Could not rename {}: {}", photo.file_name(), e));
+                break;
+            }
+
+            let old_name = photo.file_name();
+            let new_name = target.file_name().unwrap_or_default().to_string_lossy();
+            if let Some(review) = &mut self.review {
+                review
+                    .set(&old_name, ReviewStatus::Pending)
+                    .unwrap_or_default();
+                review.set(&new_name, photo.status).unwrap_or_default();
+            }
+            if let Some(entry) = self.manifest.get(&old_name) {
+                let entry = ManifestEntry {
+                    crop: new_name.to_string(),
+                    ..entry.clone()
+                };
+                manifest::append(dir, &[entry]).unwrap_or_default();
+            }
+
+            self.photo_map.remove(&photo.path);
+            self.photo_map.insert(target.clone(), index);
+            self.texture_cache.pop(&photo.path);
+            self.thumb_cache.pop(&photo.path);
+            photo.path = target;
+            renamed += 1;
+        }
+
+        self.reload_manifest(dir);
+        if self.error_message.is_none() {
+            self.status_message = Some(format!("Renamed {} crops", renamed));
+        }
+    }
+
+    /// Position of the selected photo in display order, if it is visible.
+    fn selected_position(&self) -> Option<usize> {
+        let index = self.selected_photo?;
+        self.visible.iter().position(|&i| i == index)
+    }
+
+    /// The visible photo `offset` places away from `index` in display order.
+    fn neighbour(&self, index: usize, offset: isize) -> Option<usize> {
+        let position = self.visible.iter().position(|&i| i == index)?;
+        let target = position.checked_add_signed(offset)?;
+        self.visible.get(target).copied()
+    }
+
+    /// Search, filter and sort controls above the grid.
+    fn filter_toolbar(&mut self, ui: &mut egui::Ui) {
+        let filter = &mut self.filter;
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut filter.search)
+                    .hint_text("🔍 Search file names")
+                    .desired_width(160.0),
+            );
+
+            egui::ComboBox::from_id_source("gallery_status")
+                .selected_text(filter.status.map_or("All statuses", |s| s.label()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.status, None, "All statuses");
+                    for status in [
+                        ReviewStatus::Pending,
+                        ReviewStatus::Approved,
+                        ReviewStatus::Rejected,
+                        ReviewStatus::Flagged,
+                    ] {
+                        ui.selectable_value(&mut filter.status, Some(status), status.label());
+                    }
+                });
+
+            let source_name = |path: &Path| {
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string()
+            };
+            egui::ComboBox::from_id_source("gallery_source")
+                .selected_text(
+                    filter
+                        .source
+                        .as_deref()
+                        .map_or("All sources".to_string(), source_name),
+                )
+                .width(160.0)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.source, None, "All sources");
+                    for source in &self.sources {
+                        ui.selectable_value(
+                            &mut filter.source,
+                            Some(source.clone()),
+                            source_name(source),
+                        )
+                        .on_hover_text(source.display().to_string());
+                    }
+                });
+
+            ui.add(
+                egui::DragValue::new(&mut filter.min_dimension)
+                    .clamp_range(0..=10000)
+                    .prefix("Min size ")
+                    .suffix(" px"),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Sort by")
+                .selected_text(filter.sort.label())
+                .show_ui(ui, |ui| {
+                    for key in SortKey::ALL {
+                        ui.selectable_value(&mut filter.sort, key, key.label());
+                    }
+                });
+            let direction = if filter.descending {
+                "⬇ Descending"
+            } else {
+                "⬆ Ascending"
+            };
+            if ui.button(direction).clicked() {
+                filter.descending = !filter.descending;
+            }
+            if *filter != GalleryFilter::default() && ui.button("Reset").clicked() {
+                *filter = GalleryFilter::default();
+            }
+        });
+    }
+
+    /// Handles arrow keys, Enter and Esc for the grid and the lightbox.
+    /// Returns `false` when Esc should close the gallery itself.
+    fn handle_navigation_keys(&mut self, ctx: &egui::Context) -> bool {
+        // Keys pressed for another window, like Esc in the Crop Editor,
+        // are left alone
+        let has_keyboard = widgets::window_has_keyboard(ctx, "Gallery")
+            || (self.show_lightbox && widgets::window_has_keyboard(ctx, "Image Viewer"));
+        if ctx.wants_keyboard_input() || !has_keyboard || self.visible.is_empty() {
+            return true;
+        }
+
+        let (left, right, up, down, enter, escape, select_all) = ctx.input_mut(|i| {
+            let none = egui::Modifiers::NONE;
+            (
+                i.consume_key(none, egui::Key::ArrowLeft),
+                i.consume_key(none, egui::Key::ArrowRight),
+                i.consume_key(none, egui::Key::ArrowUp),
+                i.consume_key(none, egui::Key::ArrowDown),
+                i.consume_key(none, egui::Key::Enter),
+                i.consume_key(none, egui::Key::Escape),
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::A),
+            )
+        });
+        let last = self.visible.len() - 1;
+
+        if self.show_lightbox {
+            if let Some(index) = self.selected_photo {
+                let step = if left {
+                    self.neighbour(index, -1)
+                } else if right {
+                    self.neighbour(index, 1)
+                } else {
+                    None
+                };
+                if step.is_some() {
+                    self.selected_photo = step;
+                }
+            }
+            if escape {
+                self.show_lightbox = false;
+                // Keep the grid on the photo that was being viewed
+                self.scroll_to_selected = true;
+            }
+            return true;
+        }
+
+        // Arrow keys move through the grid in display order
+        let cols = self.grid_cols.max(1);
+        let moved = match self.selected_position() {
+            None if left || right || up || down => Some(0),
+            Some(position) if left => Some(position.saturating_sub(1)),
+            Some(position) if right => Some((position + 1).min(last)),
+            Some(position) if up && position >= cols => Some(position - cols),
+            Some(position) if down && position + cols <= last => Some(position + cols),
+            _ => None,
+        };
+        if let Some(position) = moved {
+            self.selected_photo = Some(self.visible[position]);
+            self.scroll_to_selected = true;
+        }
+
+        if enter && self.selected_photo.is_some() {
+            self.show_lightbox = true;
+        }
+        if select_all {
+            self.multi_selection.extend(&self.visible);
+        }
+
+        // The first Esc drops a multi-selection, the next closes the gallery
+        if escape && !self.multi_selection.is_empty() {
+            self.multi_selection.clear();
+            return true;
+        }
+        !escape
+    }
+
+    /// Starts loading the full-resolution image at `path` in the background
+    /// unless it is already cached or on its way.
+    fn request_full_res(&mut self, ctx: &egui::Context, path: &Path) {
+        if self.texture_cache.contains(path)
+            || self.full_pending.contains(path)
+            || self.full_failed.contains(path)
+        {
+            return;
+        }
+
+        self.full_pending.insert(path.to_path_buf());
+        let tx = self.full_sender.clone();
+        let path = path.to_path_buf();
+        let max_side = ctx.input(|i| i.max_texture_side) as u32;
+        thread::spawn(move || {
+            let loaded = image::open(&path).ok().map(|mut img| {
+                // Stay within what the GPU can hold in a single texture
+                if img.width() > max_side || img.height() > max_side {
+                    img = img.thumbnail(max_side, max_side);
+                }
+                let rgba = img.to_rgba8();
+                egui::ColorImage::from_rgba_unmultiplied(
+                    [rgba.width() as usize, rgba.height() as usize],
+                    rgba.as_flat_samples().as_slice(),
+                )
+            });
+            tx.send((path, loaded)).unwrap_or_default();
+        });
+    }
+
+    /// Starts loading a display copy of a crop's source image in the
+    /// background. OpenCV is used so the orientation matches the crop
+    /// coordinates recorded by the processor.
+    fn request_source(&mut self, path: &Path) {
+        if self.source_cache.contains(path)
+            || self.source_pending.contains(path)
+            || self.source_missing.contains(path)
+        {
+            return;
+        }
+
+        self.source_pending.insert(path.to_path_buf());
+        let tx = self.source_sender.clone();
+        let path = path.to_path_buf();
+        thread::spawn(move || {
+            let loaded = imgcodecs::imread(&path.to_string_lossy(), imgcodecs::IMREAD_COLOR)
+                .ok()
+                .filter(|image| !image.empty())
+                .and_then(|image| {
+                    let size = egui::vec2(image.cols() as f32, image.rows() as f32);
+                    preview::display_image(&image)
+                        .ok()
+                        .map(|color_img| (color_img, size))
+                });
+            tx.send((path, loaded)).unwrap_or_default();
+        });
+    }
+
     fn show_lightbox_window(&mut self, ctx: &egui::Context) {
         let mut lightbox_open = true;
+        let mut review_action = review_shortcut(ctx, "Image Viewer");
+
+        // Load the current image and prefetch its neighbours
+        let mut full_tex = None;
+        let mut thumb_tex = None;
+        let previous = self.selected_photo.and_then(|i| self.neighbour(i, -1));
+        let next = self.selected_photo.and_then(|i| self.neighbour(i, 1));
+        if let Some(index) = self.selected_photo {
+            let neighbours = [Some(index), previous, next];
+            for neighbour in neighbours.into_iter().flatten() {
+                if let Some(path) = self.photos.get(neighbour).map(|p| p.path.clone()) {
+                    self.request_full_res(ctx, &path);
+                }
+            }
+
+            if let Some(photo) = self.photos.get(index) {
+                if self.lightbox_view.path.as_ref() != Some(&photo.path) {
+                    self.lightbox_view = LightboxView {
+                        path: Some(photo.path.clone()),
+                        ..Default::default()
+                    };
+                }
+                full_tex = self.texture_cache.get(&photo.path).cloned();
+                thumb_tex = self
+                    .thumb_cache
+                    .peek(&photo.path)
+                    .map(|(_, tex)| tex.clone());
+            }
+        }
+
+        // Load the source of the current crop for the side-by-side view
+        let mut source = None;
+        let entry = self
+            .selected_photo
+            .and_then(|index| self.photos.get(index))
+            .and_then(|photo| self.manifest.get(&photo.file_name()))
+            .cloned();
+        if let (true, Some(entry)) = (self.show_source, &entry) {
+            self.request_source(&entry.source);
+            source = self.source_cache.get(&entry.source).cloned();
+        }
 
         egui::Window::new("Image Viewer")
             .resizable(true)
@@ -237,8 +1536,11 @@ impl Gallery {
                 if let Some(index) = self.selected_photo {
                     if let Some(photo) = self.photos.get(index) {
                         ui.horizontal(|ui| {
-                            if ui.button("◀ Previous").clicked() && index > 0 {
-                                self.selected_photo = Some(index - 1);
+                            if ui
+                                .add_enabled(previous.is_some(), egui::Button::new("◀ Previous"))
+                                .clicked()
+                            {
+                                self.selected_photo = previous;
                             }
 
                             ui.label(
@@ -250,35 +1552,172 @@ impl Gallery {
                                     .to_string(),
                             );
 
-                            if ui.button("Next ▶").clicked() && index < self.photos.len() - 1 {
-                                self.selected_photo = Some(index + 1);
+                            if ui
+                                .add_enabled(next.is_some(), egui::Button::new("Next ▶"))
+                                .clicked()
+                            {
+                                self.selected_photo = next;
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            for status in [
+                                ReviewStatus::Approved,
+                                ReviewStatus::Rejected,
+                                ReviewStatus::Flagged,
+                            ] {
+                                let text = egui::RichText::new(format!(
+                                    "{} ({})",
+                                    status.label(),
+                                    &status.label()[..1]
+                                ));
+                                let text = match status_color(status) {
+                                    Some(color) if photo.status == status => {
+                                        text.color(color).strong()
+                                    }
+                                    _ => text,
+                                };
+                                if ui.button(text).clicked() {
+                                    review_action = Some(status);
+                                }
+                            }
+                            ui.label(format!("Status: {}", photo.status.label()));
+                        });
+
+                        let view = &mut self.lightbox_view;
+                        let show_source = &mut self.show_source;
+                        let source_missing = entry
+                            .as_ref()
+                            .is_some_and(|e| self.source_missing.contains(&e.source));
+                        let full_failed = &mut self.full_failed;
+                        ui.horizontal(|ui| {
+                            ui.add_enabled(
+                                entry.is_some(),
+                                egui::Checkbox::new(show_source, "Show source"),
+                            );
+                            if ui.selectable_label(view.zoom.is_none(), "Fit").clicked() {
+                                view.zoom = None;
+                                view.pan = egui::Vec2::ZERO;
+                            }
+                            if ui.selectable_label(view.zoom == Some(1.0), "1:1").clicked() {
+                                view.zoom = Some(1.0);
+                                view.pan = egui::Vec2::ZERO;
+                            }
+                            if let Some(zoom) = view.zoom {
+                                ui.label(format!("{:.0}%", zoom * 100.0));
+                            }
+                            if full_failed.contains(&photo.path) {
+                                ui.label("⚠ Could not load full resolution");
+                                if ui.small_button("Retry").clicked() {
+                                    full_failed.remove(&photo.path);
+                                }
+                            } else if full_tex.is_none() {
+                                ui.spinner();
+                                ui.label("Loading full resolution...");
+                            } else {
+                                ui.label("Scroll to zoom, drag to pan");
                             }
                         });
 
                         ui.separator();
 
-                        // Show the thumbnail for now (could load full-res here)
-                        if let Some(tex) = &photo.thumb_tex {
-                            let available_size = ui.available_size();
-                            let image_size = tex.size_vec2();
-                            let scale = (available_size.x / image_size.x)
-                                .min(available_size.y / image_size.y)
-                                .min(1.0);
-                            let display_size = image_size * scale;
-
-                            ui.centered_and_justified(|ui| {
-                                ui.add_sized(display_size, egui::Image::new(tex));
-                            });
+                        // Fall back to the thumbnail until the full image arrives
+                        let crop_tex = full_tex.as_ref().or(thumb_tex.as_ref());
+                        let crop_size = full_tex
+                            .as_ref()
+                            .map_or(photo.thumb_size, |t| t.size_vec2());
+
+                        match (&entry, *show_source) {
+                            (Some(entry), true) => {
+                                ui.columns(2, |columns| {
+                                    if let Some(tex) = crop_tex {
+                                        show_zoomable(&mut columns[0], tex, crop_size, view);
+                                    }
+                                    show_source_image(
+                                        &mut columns[1],
+                                        source.as_ref(),
+                                        entry,
+                                        source_missing,
+                                    );
+                                });
+                            }
+                            _ => {
+                                if let Some(tex) = crop_tex {
+                                    show_zoomable(ui, tex, crop_size, view);
+                                }
+                            }
                         }
                     }
                 }
             });
 
+        // Apply the decision and move on to the next crop
+        if let (Some(index), Some(status)) = (self.selected_photo, review_action) {
+            // Look up the next crop first: the decision may filter this one out
+            let next = self.neighbour(index, 1);
+            self.set_status(index, status);
+            if next.is_some() {
+                self.selected_photo = next;
+            }
+        }
+
         if !lightbox_open {
             self.show_lightbox = false;
         }
     }
 
+    /// Records a review decision, moving the file into or out of the
+    /// rejected folder when needed.
+    fn set_status(&mut self, index: usize, status: ReviewStatus) {
+        let Some(review) = &mut self.review else {
+            return;
+        };
+        let Some(photo) = self.photos.get_mut(index) else {
+            return;
+        };
+        if photo.status == status {
+            return;
+        }
+
+        let result = review
+            .move_for_status(&photo.path, status)
+            .and_then(|new_path| match review.set(&photo.file_name(), status) {
+                Ok(()) => Ok(new_path),
+                // Put the file back so it matches the status still in the log;
+                // if that fails too, at least keep track of where it is
+                Err(e) if new_path != photo.path => {
+                    if std::fs::rename(&new_path, &photo.path).is_err() {
+                        self.photo_map.remove(&photo.path);
+                        self.photo_map.insert(new_path.clone(), index);
+                        photo.path = new_path;
+                    }
+                    Err(e)
+                }
+                Err(e) => Err(e),
+            });
+
+        match result {
+            Ok(new_path) => {
+                if new_path != photo.path {
+                    self.photo_map.remove(&photo.path);
+                    self.photo_map.insert(new_path.clone(), index);
+                    photo.path = new_path;
+                }
+                photo.status = status;
+                self.error_message = None;
+                self.applied_filter = None;
+            }
+            Err(e) => {
+                self.error_message = Some(format!(
+                    "Could not mark {} as {}: {}",
+                    photo.file_name(),
+                    status.label(),
+                    e
+                ));
+            }
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.photos.is_empty()
     }
@@ -288,6 +1727,106 @@ impl Gallery {
     }
 
     pub fn is_loading(&self) -> bool {
-        self.is_loading
+        self.thumbnails.has_pending() || self.recrop.is_some()
+    }
+}
+
+/// Paints `tex` filling the remaining space, zoomed and panned per `view`.
+/// `image_size` is the size the texture represents at 1:1.
+fn show_zoomable(
+    ui: &mut egui::Ui,
+    tex: &egui::TextureHandle,
+    image_size: egui::Vec2,
+    view: &mut LightboxView,
+) {
+    let (rect, response) = ui.allocate_exact_size(ui.available_size(), egui::Sense::drag());
+    let fit = (rect.width() / image_size.x).min(rect.height() / image_size.y);
+    let mut zoom = view.zoom.unwrap_or(fit);
+
+    if response.hovered() {
+        let scroll = ui.input(|i| i.raw_scroll_delta.y);
+        let factor = ui.input(|i| i.zoom_delta()) * (scroll / 200.0).exp();
+        if factor != 1.0 {
+            // Keep the point under the cursor fixed while zooming
+            let new_zoom = (zoom * factor).clamp(0.05, 16.0);
+            if let Some(pointer) = response.hover_pos() {
+                let offset = pointer - rect.center() - view.pan;
+                view.pan -= offset * (new_zoom / zoom - 1.0);
+            }
+            zoom = new_zoom;
+            view.zoom = Some(zoom);
+        }
     }
+
+    if response.dragged() {
+        view.pan += response.drag_delta();
+        if view.zoom.is_none() {
+            view.zoom = Some(zoom);
+        }
+    }
+
+    let image_rect = egui::Rect::from_center_size(rect.center() + view.pan, image_size * zoom);
+    ui.painter_at(rect).image(
+        tex.id(),
+        image_rect,
+        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+        egui::Color32::WHITE,
+    );
+}
+
+/// Draws a crop's source image with the crop area and the detected face
+/// outlined.
+fn show_source_image(
+    ui: &mut egui::Ui,
+    source: Option<&SourceImage>,
+    entry: &ManifestEntry,
+    missing: bool,
+) {
+    ui.label(
+        entry
+            .source
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+    );
+
+    let Some((tex, source_size)) = source else {
+        ui.centered_and_justified(|ui| {
+            if missing {
+                ui.label(format!("Source not found: {}", entry.source.display()));
+            } else {
+                ui.spinner();
+            }
+        });
+        return;
+    };
+
+    let available = ui.available_size();
+    let scale = (available.x / source_size.x).min(available.y / source_size.y);
+    let (rect, _) = ui.allocate_exact_size(*source_size * scale, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.image(
+        tex.id(),
+        rect,
+        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+        egui::Color32::WHITE,
+    );
+
+    let to_screen = |r: &opencv::core::Rect| {
+        egui::Rect::from_min_size(
+            rect.min + egui::vec2(r.x as f32, r.y as f32) * scale,
+            egui::vec2(r.width as f32, r.height as f32) * scale,
+        )
+    };
+    painter.rect_stroke(
+        to_screen(&entry.crop_rect),
+        0.0,
+        egui::Stroke::new(2.0, CROP_RECT_COLOR),
+    );
+    painter.rect_stroke(
+        to_screen(&entry.face_rect),
+        0.0,
+        egui::Stroke::new(2.0, FACE_RECT_COLOR),
+    );
 }
@@ -1,33 +1,107 @@
+use crate::cleanup;
+use crate::detector::FaceSize;
+use crate::editor::CropEditor;
 use crate::gallery::Gallery;
-use crate::processor::{self, ProcessMessage};
+use crate::preview::Preview;
+use crate::processor::{self, ProcessControl, ProcessMessage, StageTimings};
+use crate::queue::JobQueue;
+use crate::runlog::{LogLevel, LogPanel};
+use crate::settings::{self, DetectionParams, Settings};
+use crate::sources::SourceBrowser;
+use crate::widgets::{face_selection_editor, face_size_editor};
 use eframe::egui;
 use std::path::PathBuf;
 use std::sync::mpsc::{Receiver, Sender, channel};
-use std::thread;
+use std::time::{Duration, Instant};
+
+/// Key bindings listed in the help overlay
+const SHORTCUTS: &[(&str, &str)] = &[
+    ("Ctrl+O", "Select input folder"),
+    ("Ctrl+Enter", "Process images"),
+    ("F1 / ?", "Show or hide this help"),
+    (
+        "Arrow keys",
+        "Move the gallery selection, or step through the viewer",
+    ),
+    ("Enter", "Open the selected photo in the viewer"),
+    ("Esc", "Close the viewer, then the gallery"),
+    ("A", "Approve"),
+    ("R / Delete", "Reject"),
+    ("F", "Flag"),
+    ("U", "Reset to pending"),
+];
+
+/// Shown when a run is started from the main window while the queue runs
+const QUEUE_RUNNING: &str =
+    "The job queue is running; add these folders to the queue to process them after it";
 
 pub struct HeadshotApp {
     input_path: Option<PathBuf>,
+    /// Folders and images dropped onto the window, processed along with
+    /// the input folder
+    dropped_inputs: Vec<PathBuf>,
     output_path: Option<PathBuf>,
     processing: bool,
     progress: f32,
     error_message: Option<String>,
+    status_message: Option<String>,
+    control: Option<ProcessControl>,
     rx: Option<Receiver<ProcessMessage>>,
     tx: Option<Sender<ProcessMessage>>,
     total_images: usize,
+    /// Images in the current or last run, which for a retry is only the
+    /// images without faces
+    run_total: usize,
     processed_images: usize,
     total_faces: usize,
+    total_rejected: usize,
     current_file: Option<String>,
     current_faces: Option<usize>,
-    min_neighbors: i32,
-    min_face_size: i32,
+    run_started: Option<Instant>,
+    /// Time the run spent paused, not counting a pause still going on
+    paused_total: Duration,
+    /// When the current pause began
+    paused_since: Option<Instant>,
+    /// Time the run finished, for throughput once it is complete
+    run_elapsed: Duration,
+    stage_timings: StageTimings,
+    /// Images of the last run where no face was found
+    zero_face_images: Vec<PathBuf>,
+    /// Whether the last run retried images without faces
+    retried: bool,
+    params: DetectionParams,
+    recent_folders: Vec<PathBuf>,
+    window_size: [f32; 2],
+    /// Settings as last written to disk, to detect changes
+    saved_settings: Settings,
     logo_texture: Option<egui::TextureHandle>,
     gallery: Gallery,
     show_gallery: bool,
+    preview: Preview,
+    show_preview: bool,
+    editor: Option<CropEditor>,
+    queue: JobQueue,
+    show_queue: bool,
+    log: LogPanel,
+    show_log: bool,
+    source_browser: SourceBrowser,
+    show_sources: bool,
     processing_complete: bool,
+    show_help: bool,
+    /// Files awaiting confirmation in the "Clear Output" dialog
+    pending_clear: Option<PendingClear>,
+    clear_to_trash: bool,
+}
+
+/// Headshot outputs found by "Clear Output", and how many other files the
+/// folder holds.
+struct PendingClear {
+    files: Vec<PathBuf>,
+    kept: usize,
 }
 
 impl HeadshotApp {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, settings: Settings) -> Self {
         // Load fonts
         let mut fonts = egui::FontDefinitions::default();
 
@@ -58,26 +132,60 @@ impl HeadshotApp {
         cc.egui_ctx.set_fonts(fonts);
 
         let (tx, rx) = channel();
-        Self {
+        let mut app = Self {
             input_path: None,
+            dropped_inputs: Vec::new(),
             output_path: None,
             processing: false,
             progress: 0.0,
             error_message: None,
+            status_message: None,
+            control: None,
             rx: Some(rx),
             tx: Some(tx),
             total_images: 0,
+            run_total: 0,
             processed_images: 0,
             total_faces: 0,
+            total_rejected: 0,
             current_file: None,
             current_faces: None,
-            min_neighbors: 3,
-            min_face_size: 500,
+            run_started: None,
+            paused_total: Duration::ZERO,
+            paused_since: None,
+            run_elapsed: Duration::ZERO,
+            stage_timings: StageTimings::default(),
+            zero_face_images: Vec::new(),
+            retried: false,
+            params: settings.params.clone(),
+            recent_folders: settings.recent_folders.clone(),
+            window_size: settings.window_size,
+            saved_settings: settings.clone(),
             logo_texture: None,
             gallery: Gallery::new(),
             show_gallery: false,
+            preview: Preview::new(),
+            show_preview: false,
+            editor: None,
+            queue: JobQueue::new(),
+            show_queue: false,
+            log: LogPanel::new(),
+            show_log: false,
+            source_browser: SourceBrowser::new(),
+            show_sources: false,
             processing_complete: false,
+            show_help: false,
+            pending_clear: None,
+            clear_to_trash: true,
+        };
+
+        // Folders from the last session may have been moved or unmounted
+        if let Some(path) = settings.input_path.filter(|p| p.is_dir()) {
+            app.set_input_folder(path);
         }
+        app.output_path = settings.output_path.filter(|p| p.is_dir());
+
+        app
     }
 
     fn select_input_folder(&mut self) {
@@ -85,125 +193,450 @@ impl HeadshotApp {
             .set_title("Select Input Folder")
             .pick_folder()
         {
-            self.input_path = Some(path);
-            self.error_message = None;
-            self.count_images();
+            self.set_input_folder(path);
         }
     }
 
+    fn set_input_folder(&mut self, path: PathBuf) {
+        settings::remember_folder(&mut self.recent_folders, &path);
+        self.input_path = Some(path);
+        self.error_message = None;
+        self.count_images();
+    }
+
     fn select_output_folder(&mut self) {
         if let Some(path) = rfd::FileDialog::new()
             .set_title("Select Output Folder")
             .pick_folder()
         {
-            self.output_path = Some(path);
-            self.error_message = None;
+            self.set_output_folder(path);
+        }
+    }
+
+    fn set_output_folder(&mut self, path: PathBuf) {
+        settings::remember_folder(&mut self.recent_folders, &path);
+        self.output_path = Some(path);
+        self.error_message = None;
+    }
+
+    fn settings(&self) -> Settings {
+        Settings {
+            input_path: self.input_path.clone(),
+            output_path: self.output_path.clone(),
+            recent_folders: self.recent_folders.clone(),
+            window_size: self.window_size,
+            params: self.params.clone(),
         }
     }
 
-    fn clear_output_folder(&mut self) {
+    /// Writes the settings file whenever something worth remembering has
+    /// changed. Waits for the mouse to be released so dragging a slider or
+    /// resizing the window does not write on every frame.
+    fn save_settings_if_changed(&mut self, ctx: &egui::Context) {
+        if let Some(rect) = ctx.input(|i| i.viewport().inner_rect) {
+            self.window_size = [rect.width(), rect.height()];
+        }
+        if ctx.input(|i| i.pointer.any_down()) {
+            return;
+        }
+
+        let settings = self.settings();
+        if settings != self.saved_settings {
+            if let Err(e) = settings.save() {
+                self.error_message = Some(format!("Could not save settings: {}", e));
+            }
+            // Not retried on failure, so a read-only config folder does
+            // not turn into an error on every frame
+            self.saved_settings = settings;
+        }
+    }
+
+    /// Finds the files Headshot created in the output folder and asks for
+    /// confirmation before removing them.
+    fn request_clear_output(&mut self) {
         if let Some(path) = &self.output_path {
-            if let Ok(entries) = std::fs::read_dir(path) {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        if let Ok(file_type) = entry.file_type() {
-                            if file_type.is_file() {
-                                let _ = std::fs::remove_file(entry.path());
-                            }
+            let files = cleanup::recorded_outputs(path);
+            let kept = cleanup::unrecorded_count(path, &files);
+            self.pending_clear = Some(PendingClear { files, kept });
+        }
+    }
+
+    fn clear_output_folder(&mut self, files: &[PathBuf]) {
+        let Some(path) = self.output_path.clone() else {
+            return;
+        };
+
+        match cleanup::clear_outputs(&path, files, self.clear_to_trash) {
+            Ok(count) => {
+                self.error_message = None;
+                self.status_message = Some(if self.clear_to_trash {
+                    format!("Moved {} files to {}", count, cleanup::TRASH_DIR)
+                } else {
+                    format!("Deleted {} files", count)
+                });
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Could not clear output folder: {}", e));
+            }
+        }
+
+        // Drop thumbnails of the removed crops
+        if !self.gallery.is_empty() {
+            self.gallery.load_images_from_directory(&path);
+        }
+    }
+
+    fn show_clear_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &self.pending_clear else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("Clear Output")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                if pending.files.is_empty() {
+                    ui.label("There are no Headshot outputs in this folder.");
+                } else {
+                    ui.label(format!(
+                        "{} files created by Headshot will be removed.",
+                        pending.files.len()
+                    ));
+                    ui.checkbox(
+                        &mut self.clear_to_trash,
+                        format!("Move them to {} instead of deleting", cleanup::TRASH_DIR),
+                    );
+                }
+                if pending.kept > 0 {
+                    ui.label(format!(
+                        "{} other files in the folder will be left untouched.",
+                        pending.kept
+                    ));
+                }
+
+                ui.horizontal(|ui| {
+                    if !pending.files.is_empty() {
+                        let label = if self.clear_to_trash {
+                            "Move to Trash"
+                        } else {
+                            "Delete"
+                        };
+                        if ui.button(label).clicked() {
+                            confirmed = true;
                         }
                     }
-                }
+                    if ui.button("Cancel").clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if confirmed {
+            if let Some(pending) = self.pending_clear.take() {
+                self.clear_output_folder(&pending.files);
             }
+        } else if cancelled {
+            self.pending_clear = None;
         }
     }
 
+    /// The input folder followed by everything dropped onto the window.
+    fn inputs(&self) -> Vec<PathBuf> {
+        self.input_path
+            .iter()
+            .chain(&self.dropped_inputs)
+            .cloned()
+            .collect()
+    }
+
     fn count_images(&mut self) {
-        if let Some(path) = &self.input_path {
-            if let Ok(entries) = processor::collect_image_files(path) {
-                self.total_images = entries.len();
+        if let Ok(entries) = processor::collect_image_files_from(&self.inputs()) {
+            self.total_images = entries.len();
+        }
+    }
+
+    /// Adds dropped folders and images to the processing list. Other files
+    /// are skipped and reported.
+    fn add_dropped_files(&mut self, dropped: Vec<egui::DroppedFile>) {
+        let mut ignored = 0;
+        for path in dropped.into_iter().filter_map(|file| file.path) {
+            if path.is_dir() || processor::is_valid_image(&path) {
+                if self.input_path.as_ref() != Some(&path) && !self.dropped_inputs.contains(&path) {
+                    self.dropped_inputs.push(path);
+                }
+            } else {
+                ignored += 1;
             }
         }
+
+        self.error_message = None;
+        self.status_message = (ignored > 0).then(|| {
+            format!(
+                "Ignored {} dropped file(s) that are not folders or PNG/JPEG images",
+                ignored
+            )
+        });
+        self.count_images();
     }
 
     fn process_images(&mut self) {
-        if self.input_path.is_none() || self.output_path.is_none() {
-            self.error_message = Some("Please select both input and output folders".to_string());
+        let inputs = self.inputs();
+        if inputs.is_empty() || self.output_path.is_none() {
+            self.error_message = Some(
+                "Please select an input folder or drop images, and select an output folder"
+                    .to_string(),
+            );
+            return;
+        }
+        if self.queue.is_running() {
+            self.error_message = Some(QUEUE_RUNNING.to_string());
             return;
         }
 
-        let input_path = self
-            .input_path
-            .as_ref()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
         let output_path = self
             .output_path
             .as_ref()
             .unwrap()
-            .to_str()
-            .unwrap()
+            .to_string_lossy()
             .to_string();
         let tx = self.tx.as_ref().unwrap().clone();
-        let min_neighbors = self.min_neighbors;
-        let min_face_size = self.min_face_size;
+        let options = self.params.process_options();
+        // The folders may have changed since they were counted
+        self.count_images();
+        let control = self.start_run(self.total_images);
+        self.retried = false;
+        self.source_browser
+            .set_folders(&inputs, self.output_path.as_deref());
+
+        processor::spawn_run(tx, move |tx| {
+            processor::process_images_with_progress(
+                &inputs,
+                &output_path,
+                Some(tx),
+                options,
+                Some(control),
+            )
+        });
+    }
+
+    /// Processes the images of the last run where no face was found again,
+    /// with progressively looser parameters.
+    fn retry_without_faces(&mut self) {
+        let Some(output_path) = self.output_path.clone() else {
+            return;
+        };
+        if self.queue.is_running() {
+            self.error_message = Some(QUEUE_RUNNING.to_string());
+            return;
+        }
+
+        let output_path = output_path.to_string_lossy().to_string();
+        // Every image is reported again, including on cancel, so the list
+        // is rebuilt from the run's progress
+        let images = std::mem::take(&mut self.zero_face_images);
+        let tx = self.tx.as_ref().unwrap().clone();
+        let options = self.params.process_options();
+        let control = self.start_run(images.len());
+        self.retried = true;
+
+        processor::spawn_run(tx, move |tx| {
+            processor::retry_without_faces(&images, &output_path, Some(tx), options, Some(control))
+        });
+    }
+
+    /// Resets the progress counters for a new run of `total` images and
+    /// returns its control.
+    fn start_run(&mut self, total: usize) -> ProcessControl {
+        let control = ProcessControl::default();
+        self.control = Some(control.clone());
 
         self.processing = true;
+        self.run_total = total;
         self.progress = 0.0;
         self.processed_images = 0;
         self.total_faces = 0;
+        self.total_rejected = 0;
         self.error_message = None;
+        self.status_message = None;
         self.current_file = None;
         self.current_faces = None;
+        self.processing_complete = false;
+        self.run_started = Some(Instant::now());
+        self.paused_total = Duration::ZERO;
+        self.paused_since = None;
+        self.stage_timings = StageTimings::default();
+        self.zero_face_images.clear();
+        // Crops are added to the gallery as they are written
+        self.show_output_in_gallery();
+        control
+    }
 
-        thread::spawn(move || {
-            if let Err(e) = processor::process_images_with_progress(
-                &input_path,
-                &output_path,
-                Some(tx.clone()),
-                min_neighbors,
-                min_face_size,
-            ) {
-                tx.send(ProcessMessage::Error(e.to_string()))
-                    .unwrap_or_default();
+    /// Points the gallery at the output folder unless it already shows it,
+    /// keeping the reviewer's place otherwise.
+    fn show_output_in_gallery(&mut self) {
+        if let Some(output_path) = &self.output_path {
+            if self.gallery.directory() != Some(output_path.as_path()) {
+                self.gallery.load_images_from_directory(output_path);
             }
+        }
+    }
+
+    /// Adds the current folders and parameters to the job queue.
+    fn enqueue_job(&mut self) {
+        let inputs = self.inputs();
+        let Some(output) = self.output_path.clone().filter(|_| !inputs.is_empty()) else {
+            self.error_message = Some(
+                "Please select an input folder or drop images, and select an output folder"
+                    .to_string(),
+            );
+            return;
+        };
+
+        self.queue.enqueue(inputs, output, self.params.clone());
+        self.error_message = None;
+        self.status_message = Some(format!("Added job {} to the queue", self.queue.len()));
+        self.show_queue = true;
+    }
+
+    fn cancel_processing(&mut self) {
+        if let Some(control) = &self.control {
+            control.cancel();
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        if let Some(control) = &self.control {
+            let pause = !control.is_paused();
+            control.set_paused(pause);
+            if pause {
+                self.paused_since = Some(Instant::now());
+            } else if let Some(since) = self.paused_since.take() {
+                self.paused_total += since.elapsed();
+            }
+        }
+    }
+
+    /// Time the run has been working, leaving out time spent paused.
+    fn active_elapsed(&self) -> Duration {
+        let Some(started) = self.run_started else {
+            return Duration::ZERO;
+        };
+        let paused = self.paused_total
+            + self
+                .paused_since
+                .map(|since| since.elapsed())
+                .unwrap_or_default();
+        started.elapsed().saturating_sub(paused)
+    }
+
+    fn is_paused(&self) -> bool {
+        self.control.as_ref().is_some_and(|c| c.is_paused())
+    }
+
+    /// Handles the app-wide shortcuts. Keys are consumed so the gallery does
+    /// not see them as well.
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+
+        let (open_input, process, toggle_help, close_help) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::O),
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::F1)
+                    || i.consume_key(egui::Modifiers::NONE, egui::Key::Questionmark),
+                self.show_help && i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
         });
+
+        if open_input && !self.processing {
+            self.select_input_folder();
+        }
+        if process && !self.processing && !self.queue.is_running() {
+            self.process_images();
+        }
+        if toggle_help {
+            self.show_help = !self.show_help;
+        }
+        if close_help {
+            self.show_help = false;
+        }
+    }
+
+    /// Images per second and faces per second since the run started.
+    fn throughput(&self, elapsed: Duration) -> (f64, f64) {
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        (
+            self.processed_images as f64 / secs,
+            self.total_faces as f64 / secs,
+        )
     }
 
     fn check_messages(&mut self) {
         if let Some(rx) = &self.rx {
             while let Ok(message) = rx.try_recv() {
                 match message {
-                    ProcessMessage::Progress(filename, face_count) => {
+                    ProcessMessage::Progress(report) => {
                         self.processed_images += 1;
-                        self.total_faces += face_count;
-                        self.current_file = Some(filename);
-                        self.current_faces = Some(face_count);
-                        if self.total_images > 0 {
-                            self.progress = self.processed_images as f32 / self.total_images as f32;
+                        self.total_faces += report.face_count;
+                        self.total_rejected += report.rejected_count;
+                        self.current_file = Some(report.filename);
+                        self.current_faces = Some(report.face_count);
+                        self.stage_timings.add(&report.timings);
+                        self.gallery.add_crops(&report.crops, &report.entries);
+                        self.source_browser
+                            .record(&report.source, report.face_count);
+                        if report.face_count == 0 {
+                            self.zero_face_images.push(report.source);
+                        }
+                        if self.run_total > 0 {
+                            self.progress = self.processed_images as f32 / self.run_total as f32;
                         }
                     }
                     ProcessMessage::Complete => {
                         self.processing = false;
+                        self.run_elapsed = self.active_elapsed();
                         self.progress = 1.0;
                         self.error_message = None;
                         self.current_file = None;
                         self.current_faces = None;
                         self.processing_complete = true;
+                        self.control = None;
 
-                        // Load gallery with processed images
-                        if let Some(output_path) = &self.output_path {
-                            self.gallery.load_images_from_directory(output_path);
+                        // The gallery was filled during the run
+                        if self.output_path.is_some() {
+                            self.show_output_in_gallery();
                             self.show_gallery = true;
                         }
                     }
+                    ProcessMessage::Cancelled(completed) => {
+                        self.processing = false;
+                        // The ladder did not finish, so the images can be retried again
+                        self.retried = false;
+                        self.progress = 0.0;
+                        self.current_file = None;
+                        self.current_faces = None;
+                        self.processing_complete = false;
+                        self.control = None;
+                        self.status_message = Some(format!(
+                            "Cancelled after {} of {} images",
+                            completed, self.run_total
+                        ));
+                    }
+                    ProcessMessage::Log(entry) => {
+                        self.log.push(entry);
+                    }
                     ProcessMessage::Error(error) => {
                         self.processing = false;
                         self.error_message = Some(error);
                         self.current_file = None;
                         self.current_faces = None;
                         self.processing_complete = false;
+                        self.control = None;
                     }
                 }
             }
@@ -228,9 +661,17 @@ impl eframe::App for HeadshotApp {
         }
 
         self.check_messages();
+        self.handle_shortcuts(ctx);
+
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        if !dropped.is_empty() && !self.processing {
+            self.add_dropped_files(dropped);
+        }
 
         // Update gallery
         self.gallery.update(ctx);
+        // Queued jobs wait for a run started from the main window
+        self.queue.update(ctx, !self.processing);
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -240,6 +681,13 @@ impl eframe::App for HeadshotApp {
                         let size = 32.0;
                         ui.add(egui::Image::new(logo).fit_to_exact_size(egui::vec2(size, size)));
                     }
+                    if ui
+                        .button("⌨ Shortcuts")
+                        .on_hover_text("Keyboard shortcuts (F1)")
+                        .clicked()
+                    {
+                        self.show_help = !self.show_help;
+                    }
                 });
             });
 
@@ -249,23 +697,56 @@ impl eframe::App for HeadshotApp {
                 if ui.button("Select Input Folder").clicked() {
                     self.select_input_folder();
                 }
+                if let Some(path) = recent_folder_menu(ui, "input", &self.recent_folders) {
+                    self.set_input_folder(path);
+                }
                 if let Some(path) = &self.input_path {
                     ui.label(format!("Selected: {}", path.display()));
                 }
             });
 
+            if !self.dropped_inputs.is_empty() {
+                let mut remove = None;
+                ui.label("Dropped:");
+                for (index, path) in self.dropped_inputs.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("❌").clicked() {
+                            remove = Some(index);
+                        }
+                        let kind = if path.is_dir() { "📁" } else { "🖼" };
+                        ui.label(format!("{} {}", kind, path.display()));
+                    });
+                }
+                if ui.small_button("Clear dropped").clicked() {
+                    self.dropped_inputs.clear();
+                    self.count_images();
+                } else if let Some(index) = remove {
+                    self.dropped_inputs.remove(index);
+                    self.count_images();
+                }
+            }
+
+            if self.input_path.is_some() || !self.dropped_inputs.is_empty() {
+                ui.label(format!("{} images found", self.total_images));
+            } else {
+                ui.weak("Or drop folders and images onto this window");
+            }
+
             ui.horizontal(|ui| {
                 if ui.button("Select Output Folder").clicked() {
                     self.select_output_folder();
                 }
+                if let Some(path) = recent_folder_menu(ui, "output", &self.recent_folders) {
+                    self.set_output_folder(path);
+                }
                 if let Some(path) = &self.output_path {
                     ui.label(format!("Selected: {}", path.display()));
                     ui.scope(|ui| {
                         ui.style_mut().visuals.override_text_color = Some(egui::Color32::WHITE);
                         let clear_button = egui::Button::new("Clear Output")
                             .fill(egui::Color32::from_rgb(225, 45, 0));
-                        if ui.add(clear_button).clicked() {
-                            self.clear_output_folder();
+                        if ui.add_enabled(!self.processing, clear_button).clicked() {
+                            self.request_clear_output();
                         }
                     });
                     if ui.small_button("❌").clicked() {
@@ -277,12 +758,62 @@ impl eframe::App for HeadshotApp {
             if let Some(error) = &self.error_message {
                 ui.colored_label(egui::Color32::RED, error);
             }
+            if let Some(status) = &self.status_message {
+                ui.colored_label(egui::Color32::YELLOW, status);
+            }
 
             ui.add_space(10.0);
             ui.group(|ui| {
-                ui.label("Face Detection Parameters:");
-                ui.add(egui::Slider::new(&mut self.min_neighbors, 3..=25).text("Min Neighbors"));
-                ui.add(egui::Slider::new(&mut self.min_face_size, 10..=1000).text("Min Face Size"));
+                ui.horizontal(|ui| {
+                    ui.label("Face Detection Parameters:");
+                    if ui.button("🔍 Preview").clicked() {
+                        self.show_preview = true;
+                    }
+                    if ui.button("↺ Reset to Defaults").clicked() {
+                        self.params = DetectionParams::default();
+                    }
+                });
+                ui.add(
+                    egui::Slider::new(&mut self.params.min_neighbors, 3..=25).text("Min Neighbors"),
+                );
+                face_size_editor(ui, "Min Face Size", &mut self.params.min_face_size);
+                let mut limit_max = self.params.max_face_size.is_some();
+                if ui.checkbox(&mut limit_max, "Limit max face size").changed() {
+                    self.params.max_face_size = limit_max.then_some(FaceSize::Percent(80.0));
+                }
+                if let Some(max_face_size) = &mut self.params.max_face_size {
+                    face_size_editor(ui, "Max Face Size", max_face_size);
+                }
+                if let Err(e) = self.params.process_options().detection.check_face_sizes() {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                ui.checkbox(&mut self.params.detect_profile, "Detect profile faces");
+                ui.checkbox(
+                    &mut self.params.detect_tilted,
+                    "Detect tilted faces (±15°, ±30°)",
+                );
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.params.fast_detection, "Detect on downscaled copy");
+                    ui.add_enabled(
+                        self.params.fast_detection,
+                        egui::Slider::new(&mut self.params.detect_max_dimension, 480..=4000)
+                            .suffix(" px")
+                            .text("Max Dimension"),
+                    );
+                });
+                face_selection_editor(ui, &mut self.params.face_selection);
+                ui.checkbox(
+                    &mut self.params.verify_eyes,
+                    "Verify faces by eye detection",
+                );
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.params.verify_skin, "Verify skin tone");
+                    ui.add_enabled(
+                        self.params.verify_skin,
+                        egui::Slider::new(&mut self.params.min_skin_ratio, 0.0..=1.0)
+                            .text("Min Skin Ratio"),
+                    );
+                });
             });
             ui.add_space(10.0);
 
@@ -292,11 +823,56 @@ impl eframe::App for HeadshotApp {
                         .show_percentage()
                         .animate(true),
                 );
-                ui.label(format!(
-                    "Processing: {} / {}",
-                    self.processed_images, self.total_images
-                ));
+                ui.horizontal(|ui| {
+                    let paused = self.is_paused();
+                    ui.label(format!(
+                        "{}: {} / {}",
+                        if paused { "Paused" } else { "Processing" },
+                        self.processed_images,
+                        self.run_total
+                    ));
+                    if ui
+                        .button(if paused { "▶ Resume" } else { "⏸ Pause" })
+                        .clicked()
+                    {
+                        self.toggle_pause();
+                    }
+                    if ui.button("⏹ Cancel").clicked() {
+                        self.cancel_processing();
+                    }
+                    if ui
+                        .button(format!("📸 Gallery ({})", self.gallery.photo_count()))
+                        .on_hover_text("Review crops while the run continues")
+                        .clicked()
+                    {
+                        self.show_gallery = true;
+                    }
+                });
+                if self.run_started.is_some() {
+                    let (images_per_sec, faces_per_sec) = self.throughput(self.active_elapsed());
+                    let remaining = self.run_total.saturating_sub(self.processed_images);
+                    let eta = if self.is_paused()
+                        || images_per_sec <= 0.0
+                        || self.processed_images == 0
+                    {
+                        "–".to_string()
+                    } else {
+                        processor::format_duration(Duration::from_secs_f64(
+                            remaining as f64 / images_per_sec,
+                        ))
+                    };
+                    ui.label(format!(
+                        "{:.1} images/s · {:.1} faces/s · ETA {}",
+                        images_per_sec, faces_per_sec, eta
+                    ));
+                    if self.processed_images > 0 {
+                        ui.label(format!("Stage time: {}", self.stage_timings.breakdown()));
+                    }
+                }
                 ui.label(format!("Total faces detected: {}", self.total_faces));
+                if self.total_rejected > 0 {
+                    ui.label(format!("Rejected candidates: {}", self.total_rejected));
+                }
                 if let Some(current_file) = &self.current_file {
                     if let Some(face_count) = self.current_faces {
                         ui.label(format!(
@@ -310,18 +886,70 @@ impl eframe::App for HeadshotApp {
                     ui.style_mut().visuals.override_text_color = Some(egui::Color32::WHITE);
                     let process_button = egui::Button::new("Process Images")
                         .fill(egui::Color32::from_rgb(225, 45, 0));
-                    if ui.add(process_button).clicked() {
+                    if ui
+                        .add_enabled(!self.queue.is_running(), process_button)
+                        .on_disabled_hover_text(QUEUE_RUNNING)
+                        .clicked()
+                    {
                         self.process_images();
                     }
                 });
             }
 
+            ui.horizontal(|ui| {
+                if ui
+                    .button("➕ Add to Queue")
+                    .on_hover_text("Queue the current folders and parameters as a background job")
+                    .clicked()
+                {
+                    self.enqueue_job();
+                }
+                let label = if self.queue.is_running() {
+                    format!("📋 Job Queue ({}, running)", self.queue.len())
+                } else {
+                    format!("📋 Job Queue ({})", self.queue.len())
+                };
+                if ui.button(label).clicked() {
+                    self.show_queue = true;
+                }
+
+                let warnings = self.log.count(LogLevel::Warning);
+                let errors = self.log.count(LogLevel::Error);
+                let label = if warnings + errors > 0 {
+                    format!("📜 Run Log ({} warnings, {} errors)", warnings, errors)
+                } else {
+                    "📜 Run Log".to_string()
+                };
+                if ui.button(label).clicked() {
+                    self.show_log = true;
+                }
+
+                if ui
+                    .button("🖼 Source Images")
+                    .on_hover_text("Browse the input images with the number of faces found in each")
+                    .clicked()
+                {
+                    self.show_sources = true;
+                }
+            });
+
             // Show completion status and gallery button
             if self.processing_complete {
                 ui.add_space(10.0);
                 ui.separator();
                 ui.colored_label(egui::Color32::GREEN, "✓ Processing Complete!");
                 ui.label(format!("Total faces extracted: {}", self.total_faces));
+                ui.label(format!("Rejected candidates: {}", self.total_rejected));
+                if self.processed_images > 0 {
+                    let (images_per_sec, faces_per_sec) = self.throughput(self.run_elapsed);
+                    ui.label(format!(
+                        "Took {}: {:.1} images/s · {:.1} faces/s",
+                        processor::format_duration(self.run_elapsed),
+                        images_per_sec,
+                        faces_per_sec
+                    ));
+                    ui.label(format!("Stage time: {}", self.stage_timings.breakdown()));
+                }
 
                 ui.horizontal(|ui| {
                     if ui.button("📸 View Gallery").clicked() {
@@ -336,16 +964,155 @@ impl eframe::App for HeadshotApp {
                     }
                 });
             }
+
+            // Also offered after a cancelled run, which keeps the images it
+            // did not get to
+            if !self.processing && !self.zero_face_images.is_empty() {
+                ui.horizontal(|ui| {
+                    if self.retried {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(225, 45, 0),
+                            format!(
+                                "Still no faces in {} images after every retry step",
+                                self.zero_face_images.len()
+                            ),
+                        );
+                    } else if ui
+                        .button(format!(
+                            "🔁 Retry {} Without Faces",
+                            self.zero_face_images.len()
+                        ))
+                        .on_hover_text(
+                            "Process only the images where no face was found again, \
+                                 with fewer neighbors, smaller faces, more detectors and \
+                                 no verification in turn",
+                        )
+                        .clicked()
+                    {
+                        self.retry_without_faces();
+                    }
+                });
+                egui::CollapsingHeader::new("Images without faces").show(ui, |ui| {
+                    for path in &self.zero_face_images {
+                        ui.label(path.display().to_string());
+                    }
+                });
+            }
         });
 
+        // Show detection preview if requested
+        if self.show_preview {
+            let options = self.params.process_options();
+            self.show_preview = self.preview.show(ctx, &self.inputs(), &options);
+
+            if let Some((path, faces)) = self.preview.take_edit_request() {
+                self.editor = Some(CropEditor::open(ctx, path, faces));
+            }
+        }
+
+        // Show the manual crop editor if open
+        if let Some(editor) = &mut self.editor {
+            if !editor.show(ctx, self.output_path.as_deref()) {
+                self.editor = None;
+            }
+        }
+
         // Show gallery window if requested
         if self.show_gallery {
             self.show_gallery = self.gallery.show(ctx);
         }
 
-        // Request continuous repaint while processing or gallery is loading
-        if self.processing || self.gallery.is_loading() {
+        if self.show_queue {
+            self.show_queue = self.queue.show(ctx);
+        }
+
+        if self.show_sources {
+            self.source_browser
+                .set_folders(&self.inputs(), self.output_path.as_deref());
+            self.show_sources = self.source_browser.show(ctx);
+
+            // Clicking a source shows its crops in the gallery
+            if let Some(source) = self.source_browser.take_selected_source() {
+                self.show_output_in_gallery();
+                self.gallery.show_crops_of(&source);
+                self.show_gallery = true;
+                ctx.request_repaint();
+            }
+        }
+
+        if self.show_log {
+            self.show_log = self.log.show(ctx);
+        }
+
+        if self.show_help {
+            egui::Window::new("Keyboard Shortcuts")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut self.show_help)
+                .show(ctx, |ui| {
+                    egui::Grid::new("shortcuts")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (keys, action) in SHORTCUTS {
+                                ui.strong(*keys);
+                                ui.label(*action);
+                                ui.end_row();
+                            }
+                        });
+                });
+        }
+
+        self.show_clear_confirmation(ctx);
+
+        if !self.processing && ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            show_drop_overlay(ctx);
+        }
+
+        self.save_settings_if_changed(ctx);
+
+        // Request continuous repaint while processing, a queued job runs or the
+        // gallery is loading
+        if self.processing || self.gallery.is_loading() || self.queue.is_running() {
             ctx.request_repaint();
         }
     }
 }
+
+/// Dims the window and explains what dropping will do while files are
+/// dragged over it.
+fn show_drop_overlay(ctx: &egui::Context) {
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("drop_overlay"),
+    ));
+    let rect = ctx.screen_rect();
+    painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(192));
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        "Drop folders or images to add them to the input",
+        egui::FontId::proportional(20.0),
+        egui::Color32::WHITE,
+    );
+}
+
+/// "Recent" drop-down next to a folder picker. Returns the folder the user
+/// picked, if any; folders that no longer exist are shown disabled.
+fn recent_folder_menu(ui: &mut egui::Ui, id: &str, recent: &[PathBuf]) -> Option<PathBuf> {
+    let mut picked = None;
+    ui.add_enabled_ui(!recent.is_empty(), |ui| {
+        ui.push_id(id, |ui| {
+            ui.menu_button("Recent ⏷", |ui| {
+                for path in recent {
+                    let button = egui::Button::new(path.display().to_string());
+                    if ui.add_enabled(path.is_dir(), button).clicked() {
+                        picked = Some(path.clone());
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+    });
+    picked
+}
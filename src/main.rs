@@ -1,8 +1,23 @@
+mod bulk;
+mod cleanup;
+mod detector;
+mod editor;
 mod gallery;
 mod gui;
+mod manifest;
+mod overrides;
+mod preview;
 mod processor;
+mod queue;
+mod review;
+mod runlog;
+mod settings;
+mod sources;
+mod thumbnails;
+mod watcher;
+mod widgets;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use eframe::{self, egui};
 
 #[derive(Parser, Debug)]
@@ -19,21 +34,64 @@ struct Args {
     /// Run in GUI mode
     #[arg(short, long)]
     gui: bool,
+
+    /// Minimum face size, in pixels (e.g. 100) or percent of the short side (e.g. 5%)
+    #[arg(long, default_value = "100")]
+    min_face_size: detector::FaceSize,
+
+    /// Maximum face size, in pixels or percent of the short side (no limit by default)
+    #[arg(long)]
+    max_face_size: Option<detector::FaceSize>,
+
+    /// Run detection on a copy downscaled to at most this many pixels on the longest side
+    #[arg(long)]
+    detect_size: Option<i32>,
+
+    /// Which faces to crop per image: all, largest, central, top:N or relative:PCT
+    #[arg(long, default_value = "all")]
+    select: processor::FaceSelection,
+
+    /// Also detect faces in profile (left and right)
+    #[arg(long)]
+    profile: bool,
+
+    /// Also detect faces tilted by these angles in degrees, both ways (e.g. 15,30)
+    #[arg(long, value_delimiter = ',')]
+    rotate: Vec<f64>,
+
+    /// Reject candidates without a detected eye (requires haarcascade_eye.xml)
+    #[arg(long)]
+    verify_eyes: bool,
+
+    /// Reject candidates with less than this fraction of skin-toned pixels (0.0-1.0)
+    #[arg(long)]
+    min_skin_ratio: Option<f64>,
+
+    /// CSV of per-image overrides (file,min_neighbors,min_face_size,max_face_size,select),
+    /// applied after any headshot-overrides.csv next to the images
+    #[arg(long)]
+    overrides: Option<std::path::PathBuf>,
+
+    /// Only re-process images where no face was found when they were last
+    /// processed into the output folder, with progressively looser parameters
+    #[arg(long)]
+    retry_zero: bool,
 }
 
 fn main() -> opencv::Result<()> {
     let args = Args::parse();
 
     if args.gui {
+        let settings = settings::Settings::load();
         let options = eframe::NativeOptions {
-            viewport: egui::ViewportBuilder::default().with_inner_size([800.0, 300.0]),
+            viewport: egui::ViewportBuilder::default().with_inner_size(settings.window_size),
             ..Default::default()
         };
 
         eframe::run_native(
             "Headshot",
             options,
-            Box::new(|cc| Box::new(gui::HeadshotApp::new(cc))),
+            Box::new(|cc| Box::new(gui::HeadshotApp::new(cc, settings))),
         )
         .expect("Failed to start GUI");
 
@@ -43,6 +101,32 @@ fn main() -> opencv::Result<()> {
         let input = args.input.expect("Input path is required in CLI mode");
         let output = args.output.unwrap_or_else(|| "outputs".to_string());
 
-        processor::process_images(&input, &output)
+        let detection = detector::DetectionOptions {
+            min_face_size: args.min_face_size,
+            max_face_size: args.max_face_size,
+            profile: args.profile,
+            rotation_angles: args.rotate,
+            verify_eyes: args.verify_eyes,
+            min_skin_ratio: args.min_skin_ratio,
+            detect_max_dimension: args.detect_size,
+            ..Default::default()
+        };
+        if let Err(e) = detection.check_face_sizes() {
+            Args::command()
+                .error(clap::error::ErrorKind::ArgumentConflict, e)
+                .exit();
+        }
+
+        let options = processor::ProcessOptions {
+            detection,
+            selection: args.select,
+            overrides_file: args.overrides,
+        };
+
+        if args.retry_zero {
+            processor::retry_images_without_faces(&input, &output, options)
+        } else {
+            processor::process_images(&input, &output, options)
+        }
     }
 }
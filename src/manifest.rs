@@ -0,0 +1,126 @@
+use crate::review::REJECTED_DIR;
+use opencv::core::Rect;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// File in the output folder that links each crop back to its source
+pub const MANIFEST_FILE: &str = ".headshot-manifest";
+
+/// File in the output folder with the number of faces found in each
+/// processed source, including the ones where none were found
+pub const RUNS_FILE: &str = ".headshot-runs";
+
+/// Where a crop came from.
+#[derive(Clone, Debug)]
+pub struct ManifestEntry {
+    /// File name of the crop in the output folder
+    pub crop: String,
+    /// Absolute path of the source image
+    pub source: PathBuf,
+    /// Area of the source that was cropped
+    pub crop_rect: Rect,
+    /// Face detection box inside the source
+    pub face_rect: Rect,
+}
+
+impl ManifestEntry {
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\n",
+            self.crop,
+            self.source.display(),
+            format_rect(&self.crop_rect),
+            format_rect(&self.face_rect)
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        Some(Self {
+            crop: fields.next()?.to_string(),
+            source: PathBuf::from(fields.next()?),
+            crop_rect: parse_rect(fields.next()?)?,
+            face_rect: parse_rect(fields.next()?)?,
+        })
+    }
+}
+
+fn format_rect(rect: &Rect) -> String {
+    format!("{},{},{},{}", rect.x, rect.y, rect.width, rect.height)
+}
+
+fn parse_rect(s: &str) -> Option<Rect> {
+    let values: Vec<i32> = s
+        .split(',')
+        .map(|v| v.parse().ok())
+        .collect::<Option<_>>()?;
+    match values[..] {
+        [x, y, width, height] => Some(Rect::new(x, y, width, height)),
+        _ => None,
+    }
+}
+
+/// Appends entries to the manifest in `dir`. Later lines for the same crop
+/// replace earlier ones when the manifest is loaded.
+pub fn append(dir: &Path, entries: &[ManifestEntry]) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(MANIFEST_FILE))?;
+    for entry in entries {
+        file.write_all(entry.to_line().as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Loads the manifest in `dir`, keyed by crop file name.
+pub fn load(dir: &Path) -> HashMap<String, ManifestEntry> {
+    let mut entries = HashMap::new();
+    if let Ok(contents) = fs::read_to_string(dir.join(MANIFEST_FILE)) {
+        for entry in contents.lines().filter_map(ManifestEntry::from_line) {
+            entries.insert(entry.crop.clone(), entry);
+        }
+    }
+    entries
+}
+
+/// Number of crops per source image among the entries whose crop is still
+/// in `dir` or its rejected folder. The manifest keeps entries for crops
+/// that were renamed, deleted or cleared, so those are left out.
+pub fn face_counts(dir: &Path) -> HashMap<PathBuf, usize> {
+    let mut counts = HashMap::new();
+    for entry in load(dir).into_values() {
+        if dir.join(&entry.crop).is_file() || dir.join(REJECTED_DIR).join(&entry.crop).is_file() {
+            *counts.entry(entry.source).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// Records that `source` was processed into `dir` and `faces` faces were
+/// found. Later lines for the same source replace earlier ones.
+pub fn record_run(dir: &Path, source: &Path, faces: usize) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(RUNS_FILE))?;
+    file.write_all(format!("{}\t{}\n", source.display(), faces).as_bytes())
+}
+
+/// Number of faces found in each source when it was last processed into
+/// `dir`, keyed by its absolute path.
+pub fn load_runs(dir: &Path) -> HashMap<PathBuf, usize> {
+    let mut runs = HashMap::new();
+    if let Ok(contents) = fs::read_to_string(dir.join(RUNS_FILE)) {
+        for line in contents.lines() {
+            if let Some((source, faces)) = line.rsplit_once('\t') {
+                if let Ok(faces) = faces.parse() {
+                    runs.insert(PathBuf::from(source), faces);
+                }
+            }
+        }
+    }
+    runs
+}
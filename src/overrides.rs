@@ -0,0 +1,347 @@
+use crate::detector::{DetectionOptions, FaceSize};
+use crate::processor::{FaceSelection, ProcessOptions};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File next to the images that holds their per-image overrides
+pub const OVERRIDES_FILE: &str = "headshot-overrides.csv";
+
+/// Column names, in the order they are written
+const COLUMNS: [&str; 5] = [
+    "file",
+    "min_neighbors",
+    "min_face_size",
+    "max_face_size",
+    "select",
+];
+
+/// Detection settings that replace the batch settings for a single image.
+/// Unset fields keep the batch value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParamOverrides {
+    pub min_neighbors: Option<i32>,
+    pub min_face_size: Option<FaceSize>,
+    pub max_face_size: Option<FaceSize>,
+    pub selection: Option<FaceSelection>,
+}
+
+impl ParamOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The batch options with these overrides applied.
+    pub fn apply(&self, options: &ProcessOptions) -> ProcessOptions {
+        let mut options = options.clone();
+        let detection = &mut options.detection;
+        if let Some(min_neighbors) = self.min_neighbors {
+            detection.min_neighbors = min_neighbors;
+        }
+        if let Some(min_face_size) = self.min_face_size {
+            detection.min_face_size = min_face_size;
+        }
+        if let Some(max_face_size) = self.max_face_size {
+            detection.max_face_size = Some(max_face_size);
+        }
+        if let Some(selection) = self.selection {
+            options.selection = selection;
+        }
+        options
+    }
+
+    /// Cells for every column but `file`; unset fields are left empty.
+    fn cells(&self) -> [String; 4] {
+        fn cell<T: ToString>(value: Option<T>) -> String {
+            value.map(|v| v.to_string()).unwrap_or_default()
+        }
+        [
+            cell(self.min_neighbors),
+            cell(self.min_face_size),
+            cell(self.max_face_size),
+            cell(self.selection),
+        ]
+    }
+}
+
+impl std::fmt::Display for ParamOverrides {
+    /// Lists the set fields, e.g. `min_neighbors=2, min_face_size=5%`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let set: Vec<String> = COLUMNS[1..]
+            .iter()
+            .zip(self.cells())
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(column, value)| format!("{}={}", column, value))
+            .collect();
+        write!(f, "{}", set.join(", "))
+    }
+}
+
+/// Overrides for a batch of images, keyed by resolved image path.
+#[derive(Default)]
+pub struct OverrideTable {
+    entries: HashMap<PathBuf, ParamOverrides>,
+}
+
+impl OverrideTable {
+    /// Reads the overrides file in the folder of each image. Lines that
+    /// cannot be parsed are skipped and returned as messages.
+    pub fn for_images(images: &[PathBuf]) -> (Self, Vec<String>) {
+        let mut folders: Vec<&Path> = images.iter().filter_map(|p| p.parent()).collect();
+        folders.sort();
+        folders.dedup();
+
+        let mut table = Self::default();
+        let mut problems = Vec::new();
+        for folder in folders {
+            let file = folder.join(OVERRIDES_FILE);
+            if file.is_file() {
+                problems.extend(table.load_file(&file));
+            }
+        }
+        (table, problems)
+    }
+
+    /// Adds the overrides in the CSV file at `path`, replacing earlier ones
+    /// for the same images. File names are relative to the file's folder.
+    /// Returns a message for each line that could not be parsed.
+    pub fn load_file(&mut self, path: &Path) -> Vec<String> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => return vec![format!("Could not read {}: {}", path.display(), e)],
+        };
+        let folder = path.parent().unwrap_or(Path::new("."));
+        let (rows, mut problems) = parse(&contents);
+        for (file, overrides) in rows {
+            self.entries.insert(resolve(&folder.join(file)), overrides);
+        }
+        for problem in &mut problems {
+            *problem = format!("{}: {}", path.display(), problem);
+        }
+        problems
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, image: &Path) -> Option<&ParamOverrides> {
+        self.entries.get(&resolve(image))
+    }
+}
+
+/// The saved overrides for one image, if any.
+pub fn load_for(image: &Path) -> ParamOverrides {
+    let Some(name) = image.file_name() else {
+        return ParamOverrides::default();
+    };
+    let file = image.with_file_name(OVERRIDES_FILE);
+    let contents = fs::read_to_string(file).unwrap_or_default();
+    parse(&contents)
+        .0
+        .remove(&*name.to_string_lossy())
+        .unwrap_or_default()
+}
+
+/// Stores the overrides for one image in the overrides file of its folder,
+/// removing its line if `overrides` is empty. Other lines are kept.
+pub fn save_for(image: &Path, overrides: &ParamOverrides) -> io::Result<()> {
+    let name = image
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file"))?
+        .to_string_lossy()
+        .to_string();
+    let file = image.with_file_name(OVERRIDES_FILE);
+    let contents = fs::read_to_string(&file).unwrap_or_default();
+
+    let mut rows: BTreeMap<String, ParamOverrides> = parse(&contents).0.into_iter().collect();
+    if overrides.is_empty() {
+        rows.remove(&name);
+    } else {
+        rows.insert(name, overrides.clone());
+    }
+
+    if rows.is_empty() {
+        return match fs::remove_file(&file) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let mut contents = COLUMNS.join(",");
+    contents.push('\n');
+    for (name, overrides) in rows {
+        let [a, b, c, d] = overrides.cells();
+        let row = [quote(&name), a, b, c, d];
+        contents.push_str(&row.join(","));
+        contents.push('\n');
+    }
+    fs::write(file, contents)
+}
+
+/// Parses an overrides file: a header naming some of [`COLUMNS`] in any
+/// order, then one line per image. Returns the rows by file name and a
+/// message for each line that was skipped.
+fn parse(contents: &str) -> (HashMap<String, ParamOverrides>, Vec<String>) {
+    let mut rows = HashMap::new();
+    let mut problems = Vec::new();
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let Some((_, header)) = lines.next() else {
+        return (rows, problems);
+    };
+    // Excel starts UTF-8 CSV files with a byte order mark
+    let header: Vec<String> = split_csv(header.trim_start_matches('\u{feff}'))
+        .into_iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    if let Some(unknown) = header.iter().find(|h| !COLUMNS.contains(&h.as_str())) {
+        problems.push(format!("unknown column '{}'", unknown));
+        return (rows, problems);
+    }
+    if !header.iter().any(|h| h == "file") {
+        problems.push("missing 'file' column".to_string());
+        return (rows, problems);
+    }
+
+    for (number, line) in lines {
+        let mut file = None;
+        let mut overrides = ParamOverrides::default();
+        let parsed = header
+            .iter()
+            .zip(split_csv(line))
+            .filter(|(_, value)| !value.trim().is_empty())
+            .try_for_each(|(column, value)| {
+                let value = value.trim();
+                match column.as_str() {
+                    "file" => file = Some(value.to_string()),
+                    "min_neighbors" => {
+                        overrides.min_neighbors = Some(
+                            value
+                                .parse()
+                                .map_err(|_| format!("invalid min_neighbors: {}", value))?,
+                        )
+                    }
+                    "min_face_size" => overrides.min_face_size = Some(value.parse()?),
+                    "max_face_size" => overrides.max_face_size = Some(value.parse()?),
+                    "select" => overrides.selection = Some(value.parse()?),
+                    _ => {}
+                }
+                Ok::<(), String>(())
+            });
+        let parsed =
+            parsed.and_then(
+                |()| match (overrides.min_face_size, overrides.max_face_size) {
+                    (Some(min_face_size), Some(max_face_size)) => DetectionOptions {
+                        min_face_size,
+                        max_face_size: Some(max_face_size),
+                        ..Default::default()
+                    }
+                    .check_face_sizes(),
+                    _ => Ok(()),
+                },
+            );
+
+        match (parsed, file) {
+            (Ok(()), Some(file)) => {
+                rows.insert(file, overrides);
+            }
+            (Ok(()), None) => problems.push(format!("line {}: no file name", number + 1)),
+            (Err(e), _) => problems.push(format!("line {}: {}", number + 1, e)),
+        }
+    }
+    (rows, problems)
+}
+
+/// Splits one CSV line into fields, honouring double-quoted fields with
+/// `""` for a literal quote.
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn quote(field: &str) -> String {
+    if field.contains([',', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Resolves an image path the same way for the table and for lookups.
+fn resolve(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_csv_handles_quoted_fields() {
+        assert_eq!(
+            split_csv(r#"a,"b, c","say ""hi""",,"#),
+            ["a", "b, c", "say \"hi\"", "", ""]
+        );
+    }
+
+    #[test]
+    fn quoted_file_names_round_trip() {
+        let name = "group, \"final\".jpg";
+        let contents = format!("file,min_neighbors\n{},3\n", quote(name));
+        let (rows, problems) = parse(&contents);
+        assert!(problems.is_empty());
+        assert_eq!(rows[name].min_neighbors, Some(3));
+    }
+
+    #[test]
+    fn header_may_start_with_byte_order_mark() {
+        let (rows, problems) = parse("\u{feff}file,select\nportrait.jpg,top:2\n");
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(rows["portrait.jpg"].selection, Some(FaceSelection::TopN(2)));
+    }
+
+    #[test]
+    fn unknown_column_rejects_the_file() {
+        let (rows, problems) = parse("file,size\nportrait.jpg,3\n");
+        assert!(rows.is_empty());
+        assert_eq!(problems, ["unknown column 'size'"]);
+    }
+
+    #[test]
+    fn minimum_above_maximum_skips_the_line() {
+        let contents = "file,min_face_size,max_face_size\n\
+                        too_small.jpg,200,100\n\
+                        fine.jpg,50,100\n\
+                        mixed.jpg,200,10%\n";
+        let (rows, problems) = parse(contents);
+        assert!(!rows.contains_key("too_small.jpg"));
+        assert_eq!(rows["fine.jpg"].min_face_size, Some(FaceSize::Pixels(50)));
+        // Pixels and percentages can only be compared per image
+        assert!(rows.contains_key("mixed.jpg"));
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("line 2: "), "{}", problems[0]);
+    }
+}
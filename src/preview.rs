@@ -0,0 +1,509 @@
+use crate::detector::{FaceDetector, FaceSize};
+use crate::overrides::{self, OVERRIDES_FILE, ParamOverrides};
+use crate::processor::{self, FaceBoxes, ProcessOptions};
+use crate::widgets::{face_selection_editor, face_size_editor};
+use eframe::egui;
+use opencv::core::AlgorithmHint;
+use opencv::{
+    Result,
+    core::{Mat, Rect, Size},
+    imgcodecs, imgproc,
+    prelude::*,
+};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+
+/// Longest side of the image shown in the preview window
+const PREVIEW_MAX_DIMENSION: i32 = 1024;
+
+const FACE_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 200, 80);
+const CROP_COLOR: egui::Color32 = egui::Color32::from_rgb(225, 45, 0);
+const REJECTED_COLOR: egui::Color32 = egui::Color32::from_rgb(150, 150, 150);
+
+/// A detection request: the sample image and the parameters to run with.
+#[derive(Clone, PartialEq)]
+struct PreviewRequest {
+    path: PathBuf,
+    options: ProcessOptions,
+}
+
+struct PreviewResult {
+    /// The sample the result belongs to
+    path: PathBuf,
+    /// Downscaled copy of the sample for display, if it could be read
+    image: Option<egui::ColorImage>,
+    /// Size of the sample at full resolution
+    source_size: egui::Vec2,
+    boxes: std::result::Result<FaceBoxes, String>,
+}
+
+/// Runs detection on a single sample image from the input folder and draws
+/// the resulting boxes, re-running whenever the parameters change.
+pub struct Preview {
+    /// Input folders and files the samples are drawn from
+    inputs: Vec<PathBuf>,
+    samples: Vec<PathBuf>,
+    selected: usize,
+    texture: Option<egui::TextureHandle>,
+    source_size: egui::Vec2,
+    boxes: Option<FaceBoxes>,
+    error: Option<String>,
+    /// The sample `texture` and `boxes` belong to, which lags behind the
+    /// selected one while its detection runs
+    shown: Option<PathBuf>,
+    /// The most recent request sent to the worker
+    requested: Option<PreviewRequest>,
+    in_flight: bool,
+    /// Set when the user asks to hand-edit the boxes of the current sample
+    edit_request: Option<(PathBuf, Vec<Rect>)>,
+    /// Overrides being edited for the current sample, used for its preview
+    overrides: ParamOverrides,
+    /// The sample the overrides belong to and what is saved for it
+    saved_overrides: Option<(PathBuf, ParamOverrides)>,
+    overrides_error: Option<String>,
+    result_sender: Sender<PreviewResult>,
+    result_receiver: Receiver<PreviewResult>,
+}
+
+impl Preview {
+    pub fn new() -> Self {
+        let (tx, rx) = channel();
+        Self {
+            inputs: Vec::new(),
+            samples: Vec::new(),
+            selected: 0,
+            texture: None,
+            source_size: egui::Vec2::ZERO,
+            boxes: None,
+            error: None,
+            shown: None,
+            requested: None,
+            in_flight: false,
+            edit_request: None,
+            overrides: ParamOverrides::default(),
+            saved_overrides: None,
+            overrides_error: None,
+            result_sender: tx,
+            result_receiver: rx,
+        }
+    }
+
+    fn set_inputs(&mut self, inputs: &[PathBuf]) {
+        if self.inputs == inputs {
+            return;
+        }
+
+        self.inputs = inputs.to_vec();
+        self.samples = processor::collect_image_files_from(inputs).unwrap_or_default();
+        self.samples.sort();
+        self.selected = 0;
+        self.texture = None;
+        self.boxes = None;
+        self.error = None;
+        self.shown = None;
+    }
+
+    /// Collects a finished detection run, if any, and starts a new one when
+    /// the sample or the parameters differ from the last request.
+    fn update(&mut self, ctx: &egui::Context, options: &ProcessOptions) {
+        while let Ok(result) = self.result_receiver.try_recv() {
+            self.in_flight = false;
+            self.texture = result.image.map(|image| {
+                ctx.load_texture("detection_preview", image, egui::TextureOptions::LINEAR)
+            });
+            self.source_size = result.source_size;
+            self.shown = Some(result.path);
+            match result.boxes {
+                Ok(boxes) => {
+                    self.boxes = Some(boxes);
+                    self.error = None;
+                }
+                Err(e) => {
+                    self.boxes = None;
+                    self.error = Some(e);
+                }
+            }
+        }
+
+        let Some(path) = self.samples.get(self.selected) else {
+            return;
+        };
+        let request = PreviewRequest {
+            path: path.clone(),
+            options: self.overrides.apply(options),
+        };
+
+        // Only one run at a time; slider drags queue up as the latest request
+        // and are picked up once the current run finishes.
+        if self.in_flight || self.requested.as_ref() == Some(&request) {
+            return;
+        }
+
+        self.requested = Some(request.clone());
+        self.in_flight = true;
+        let tx = self.result_sender.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            tx.send(run_preview(&request)).unwrap_or_default();
+            ctx.request_repaint();
+        });
+    }
+
+    /// Loads the saved overrides when a different sample is selected.
+    fn load_overrides(&mut self) {
+        let Some(path) = self.samples.get(self.selected) else {
+            return;
+        };
+        if self
+            .saved_overrides
+            .as_ref()
+            .is_some_and(|(p, _)| p == path)
+        {
+            return;
+        }
+        let saved = overrides::load_for(path);
+        self.overrides = saved.clone();
+        self.saved_overrides = Some((path.clone(), saved));
+        self.overrides_error = None;
+    }
+
+    /// Editor for the current sample's overrides. Each setting can be taken
+    /// from the batch or set for this image only.
+    fn overrides_editor(&mut self, ui: &mut egui::Ui, options: &ProcessOptions) {
+        let saved = self
+            .saved_overrides
+            .as_ref()
+            .map(|(_, saved)| saved.clone())
+            .unwrap_or_default();
+        let title = if saved.is_empty() {
+            "Overrides for this image".to_string()
+        } else {
+            format!("Overrides for this image ({})", saved)
+        };
+
+        egui::CollapsingHeader::new(title)
+            .id_source("preview_overrides")
+            .show(ui, |ui| {
+                let batch = &options.detection;
+                let overrides = &mut self.overrides;
+
+                let mut enabled = overrides.min_neighbors.is_some();
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        overrides.min_neighbors = enabled.then_some(batch.min_neighbors);
+                    }
+                    match &mut overrides.min_neighbors {
+                        Some(n) => {
+                            ui.add(egui::Slider::new(n, 1..=25).text("Min Neighbors"));
+                        }
+                        None => {
+                            ui.weak(format!("Min Neighbors: {} (batch)", batch.min_neighbors));
+                        }
+                    }
+                });
+
+                let mut enabled = overrides.min_face_size.is_some();
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        overrides.min_face_size = enabled.then_some(batch.min_face_size);
+                    }
+                    match &mut overrides.min_face_size {
+                        Some(size) => face_size_editor(ui, "Min Face Size", size),
+                        None => {
+                            ui.weak(format!("Min Face Size: {} (batch)", batch.min_face_size));
+                        }
+                    }
+                });
+
+                let mut enabled = overrides.max_face_size.is_some();
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        overrides.max_face_size = enabled
+                            .then_some(batch.max_face_size.unwrap_or(FaceSize::Percent(80.0)));
+                    }
+                    match &mut overrides.max_face_size {
+                        Some(size) => face_size_editor(ui, "Max Face Size", size),
+                        None => {
+                            ui.weak(match batch.max_face_size {
+                                Some(size) => format!("Max Face Size: {} (batch)", size),
+                                None => "Max Face Size: no limit (batch)".to_string(),
+                            });
+                        }
+                    }
+                });
+
+                let mut enabled = overrides.selection.is_some();
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        overrides.selection = enabled.then_some(options.selection);
+                    }
+                    match &mut overrides.selection {
+                        Some(selection) => face_selection_editor(ui, selection),
+                        None => {
+                            ui.weak(format!(
+                                "Faces Per Image: {} (batch)",
+                                options.selection.label()
+                            ));
+                        }
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    let changed = self.overrides != saved;
+                    if ui
+                        .add_enabled(changed, egui::Button::new("💾 Save"))
+                        .on_hover_text(format!(
+                            "Store in {} next to the image; used by every run",
+                            OVERRIDES_FILE
+                        ))
+                        .clicked()
+                    {
+                        self.save_overrides(self.overrides.clone());
+                    }
+                    if ui
+                        .add_enabled(changed, egui::Button::new("Revert"))
+                        .clicked()
+                    {
+                        self.overrides = saved.clone();
+                    }
+                    if ui
+                        .add_enabled(!saved.is_empty(), egui::Button::new("Remove"))
+                        .clicked()
+                    {
+                        self.save_overrides(ParamOverrides::default());
+                    }
+                });
+                if let Some(error) = &self.overrides_error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+            });
+    }
+
+    /// Writes `values` as the current sample's overrides; empty values
+    /// remove them.
+    fn save_overrides(&mut self, values: ParamOverrides) {
+        let Some((path, saved)) = self.saved_overrides.take() else {
+            return;
+        };
+        match overrides::save_for(&path, &values) {
+            Ok(()) => {
+                self.overrides = values.clone();
+                self.saved_overrides = Some((path, values));
+                self.overrides_error = None;
+            }
+            Err(e) => {
+                self.overrides_error = Some(format!("Could not save overrides: {}", e));
+                self.saved_overrides = Some((path, saved));
+            }
+        }
+    }
+
+    /// Returns the sample and its detected faces if "Edit Crops" was clicked
+    /// since the last call.
+    pub fn take_edit_request(&mut self) -> Option<(PathBuf, Vec<Rect>)> {
+        self.edit_request.take()
+    }
+
+    /// Shows the preview window. Returns `false` once the user closes it.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        inputs: &[PathBuf],
+        options: &ProcessOptions,
+    ) -> bool {
+        self.set_inputs(inputs);
+        self.load_overrides();
+        self.update(ctx, options);
+
+        let mut preview_open = true;
+
+        egui::Window::new("Detection Preview")
+            .resizable(true)
+            .default_size([700.0, 600.0])
+            .open(&mut preview_open)
+            .show(ctx, |ui| {
+                if self.samples.is_empty() {
+                    ui.centered_and_justified(|ui| {
+                        ui.label("Select an input folder or drop images to preview detection");
+                    });
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("◀").clicked() && self.selected > 0 {
+                        self.selected -= 1;
+                    }
+
+                    egui::ComboBox::from_id_source("preview_sample")
+                        .selected_text(file_name(&self.samples[self.selected]))
+                        .width(300.0)
+                        .show_ui(ui, |ui| {
+                            for (index, path) in self.samples.iter().enumerate() {
+                                ui.selectable_value(&mut self.selected, index, file_name(path));
+                            }
+                        });
+
+                    if ui.button("▶").clicked() && self.selected + 1 < self.samples.len() {
+                        self.selected += 1;
+                    }
+
+                    if self.in_flight {
+                        ui.spinner();
+                    }
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        let current = self.shown.as_ref().filter(|shown| {
+                            !self.in_flight && self.samples.get(self.selected) == Some(*shown)
+                        });
+                        if ui
+                            .add_enabled(current.is_some(), egui::Button::new("✏ Edit Crops"))
+                            .on_disabled_hover_text("Wait for detection to finish")
+                            .clicked()
+                        {
+                            if let Some(path) = current {
+                                let faces = self
+                                    .boxes
+                                    .as_ref()
+                                    .map(|boxes| boxes.faces.clone())
+                                    .unwrap_or_default();
+                                self.edit_request = Some((path.clone(), faces));
+                            }
+                        }
+                    });
+                });
+
+                ui.horizontal(|ui| {
+                    ui.colored_label(FACE_COLOR, "■ Face");
+                    ui.colored_label(CROP_COLOR, "■ Crop");
+                    ui.colored_label(REJECTED_COLOR, "■ Rejected");
+                    if let Some(boxes) = &self.boxes {
+                        ui.separator();
+                        ui.label(format!(
+                            "{} faces, {} rejected",
+                            boxes.faces.len(),
+                            boxes.rejected.len()
+                        ));
+                    }
+                });
+
+                self.overrides_editor(ui, options);
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                ui.separator();
+
+                if let Some(tex) = &self.texture {
+                    let available = ui.available_size();
+                    let image_size = tex.size_vec2();
+                    let scale = (available.x / image_size.x)
+                        .min(available.y / image_size.y)
+                        .min(1.0);
+                    let response =
+                        ui.add(egui::Image::new(tex).fit_to_exact_size(image_size * scale));
+
+                    if let Some(boxes) = &self.boxes {
+                        let painter = ui.painter_at(response.rect);
+                        // Boxes are in source pixels; map them onto the widget
+                        let to_screen = response.rect.width() / self.source_size.x;
+                        let draw = |rect: &Rect, color: egui::Color32| {
+                            let min = response.rect.min
+                                + egui::vec2(rect.x as f32, rect.y as f32) * to_screen;
+                            let size =
+                                egui::vec2(rect.width as f32, rect.height as f32) * to_screen;
+                            painter.rect_stroke(
+                                egui::Rect::from_min_size(min, size),
+                                0.0,
+                                egui::Stroke::new(2.0, color),
+                            );
+                        };
+
+                        for rect in &boxes.rejected {
+                            draw(rect, REJECTED_COLOR);
+                        }
+                        for rect in &boxes.crops {
+                            draw(rect, CROP_COLOR);
+                        }
+                        for rect in &boxes.faces {
+                            draw(rect, FACE_COLOR);
+                        }
+                    }
+                } else if self.in_flight {
+                    ui.centered_and_justified(|ui| {
+                        ui.spinner();
+                    });
+                }
+            });
+
+        preview_open
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Loads the sample, runs detection with the requested parameters and
+/// prepares a display copy.
+fn run_preview(request: &PreviewRequest) -> PreviewResult {
+    let image = match imgcodecs::imread(&request.path.to_string_lossy(), imgcodecs::IMREAD_COLOR) {
+        Ok(image) if !image.empty() => image,
+        _ => {
+            return PreviewResult {
+                path: request.path.clone(),
+                image: None,
+                source_size: egui::Vec2::ZERO,
+                boxes: Err(format!("Could not read {}", request.path.display())),
+            };
+        }
+    };
+
+    let boxes = FaceDetector::new(request.options.detection.clone())
+        .and_then(|mut detector| {
+            processor::detect_faces(&image, &mut detector, request.options.selection)
+        })
+        .map_err(|e| e.to_string());
+
+    PreviewResult {
+        path: request.path.clone(),
+        image: display_image(&image).ok(),
+        source_size: egui::vec2(image.cols() as f32, image.rows() as f32),
+        boxes,
+    }
+}
+
+/// Converts a BGR image to an RGBA `ColorImage` no larger than
+/// `PREVIEW_MAX_DIMENSION` on its longest side.
+pub fn display_image(image: &Mat) -> Result<egui::ColorImage> {
+    let longest = image.cols().max(image.rows());
+    let scale = (PREVIEW_MAX_DIMENSION as f64 / longest as f64).min(1.0);
+
+    let mut small = Mat::default();
+    imgproc::resize(
+        image,
+        &mut small,
+        Size::default(),
+        scale,
+        scale,
+        imgproc::INTER_AREA,
+    )?;
+
+    let mut rgba = Mat::default();
+    imgproc::cvt_color(
+        &small,
+        &mut rgba,
+        imgproc::COLOR_BGR2RGBA,
+        0,
+        AlgorithmHint::ALGO_HINT_DEFAULT,
+    )?;
+
+    Ok(egui::ColorImage::from_rgba_unmultiplied(
+        [rgba.cols() as usize, rgba.rows() as usize],
+        rgba.data_bytes()?,
+    ))
+}
@@ -1,85 +1,941 @@
+use crate::detector::{DetectionOptions, FaceDetector, FaceSize};
+use crate::manifest::{self, ManifestEntry};
+use crate::overrides::OverrideTable;
+use crate::runlog::{LogEntry, LogLevel};
+use indicatif::{ProgressBar, ProgressStyle};
 use opencv::core::AlgorithmHint;
 use opencv::{
     Result,
-    core::{Mat, Rect, Size, Vector},
+    core::{Mat, Rect, Vector},
     imgcodecs, imgproc,
-    objdetect::CascadeClassifier,
     prelude::*,
 };
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
-use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Sender, channel};
+use std::thread;
+use std::time::{Duration, Instant};
 
 pub enum ProcessMessage {
-    Progress(String, usize), // filename, face count for this image
+    Progress(ImageReport),
     Complete,
+    /// The run was cancelled after this many files were completed
+    Cancelled(usize),
     Error(String),
+    /// An event for the run log
+    Log(LogEntry),
 }
 
-pub fn process_images(input: &str, output: &str) -> Result<()> {
-    process_images_with_progress(input, output, None, 8, 100)
+/// Shared handle for cancelling or pausing a running batch. Clones refer to
+/// the same batch; the processor checks it between images.
+#[derive(Clone, Default)]
+pub struct ProcessControl {
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
 }
 
-pub fn process_images_with_progress(
+impl ProcessControl {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Blocks while the batch is paused. Returns `false` if it was cancelled.
+    fn wait_while_paused(&self) -> bool {
+        while self.is_paused() && !self.is_cancelled() {
+            thread::sleep(Duration::from_millis(100));
+        }
+        !self.is_cancelled()
+    }
+}
+
+/// Which of the detected faces in an image get cropped.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FaceSelection {
+    #[default]
+    All,
+    Largest,
+    /// The face whose center is closest to the image center
+    MostCentral,
+    /// The N largest faces
+    TopN(usize),
+    /// Faces whose area is at least this percentage of the largest face
+    RelativeToLargest(f64),
+}
+
+impl FaceSelection {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FaceSelection::All => "All faces",
+            FaceSelection::Largest => "Largest only",
+            FaceSelection::MostCentral => "Most central only",
+            FaceSelection::TopN(_) => "Top N by size",
+            FaceSelection::RelativeToLargest(_) => "Relative to largest",
+        }
+    }
+
+    /// Filters `faces` according to the policy. Size-based policies return
+    /// the faces largest first so `_face_1` is always the main subject.
+    fn apply(&self, mut faces: Vec<Rect>, image: &Mat) -> Vec<Rect> {
+        if faces.is_empty() {
+            return faces;
+        }
+
+        match *self {
+            FaceSelection::All => faces,
+            FaceSelection::Largest => {
+                faces.sort_by_key(|f| std::cmp::Reverse(f.area()));
+                faces.truncate(1);
+                faces
+            }
+            FaceSelection::MostCentral => {
+                let cx = image.cols() as f64 / 2.0;
+                let cy = image.rows() as f64 / 2.0;
+                let distance = |f: &Rect| {
+                    let dx = f.x as f64 + f.width as f64 / 2.0 - cx;
+                    let dy = f.y as f64 + f.height as f64 / 2.0 - cy;
+                    dx * dx + dy * dy
+                };
+                faces.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+                faces.truncate(1);
+                faces
+            }
+            FaceSelection::TopN(n) => {
+                faces.sort_by_key(|f| std::cmp::Reverse(f.area()));
+                faces.truncate(n);
+                faces
+            }
+            FaceSelection::RelativeToLargest(pct) => {
+                faces.sort_by_key(|f| std::cmp::Reverse(f.area()));
+                let threshold = faces[0].area() as f64 * pct / 100.0;
+                faces.retain(|f| f.area() as f64 >= threshold);
+                faces
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for FaceSelection {
+    /// Formats the selection in the form accepted by `from_str`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FaceSelection::All => write!(f, "all"),
+            FaceSelection::Largest => write!(f, "largest"),
+            FaceSelection::MostCentral => write!(f, "central"),
+            FaceSelection::TopN(n) => write!(f, "top:{}", n),
+            FaceSelection::RelativeToLargest(pct) => write!(f, "relative:{}", pct),
+        }
+    }
+}
+
+impl std::str::FromStr for FaceSelection {
+    type Err = String;
+
+    /// Parses `all`, `largest`, `central`, `top:N` or `relative:PCT`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (name, value) = match s.split_once(':') {
+            Some((name, value)) => (name, Some(value)),
+            None => (s, None),
+        };
+
+        match (name.to_lowercase().as_str(), value) {
+            ("all", None) => Ok(FaceSelection::All),
+            ("largest", None) => Ok(FaceSelection::Largest),
+            ("central", None) => Ok(FaceSelection::MostCentral),
+            ("top", Some(n)) => match n.parse() {
+                Ok(0) => Err("face count must be at least 1".to_string()),
+                Ok(count) => Ok(FaceSelection::TopN(count)),
+                Err(_) => Err(format!("invalid face count: {}", n)),
+            },
+            ("relative", Some(pct)) => match pct.trim_end_matches('%').parse::<f64>() {
+                Ok(value) if value > 0.0 && value <= 100.0 => {
+                    Ok(FaceSelection::RelativeToLargest(value))
+                }
+                Ok(_) => Err(format!(
+                    "percentage must be above 0 and at most 100: {}",
+                    pct
+                )),
+                Err(_) => Err(format!("invalid percentage: {}", pct)),
+            },
+            _ => Err(format!(
+                "unknown selection '{}' (expected all, largest, central, top:N or relative:PCT)",
+                s
+            )),
+        }
+    }
+}
+
+/// How much of the surroundings a crop keeps around the face.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Framing {
+    Tight,
+    #[default]
+    Standard,
+    Loose,
+}
+
+impl Framing {
+    pub const ALL: [Framing; 3] = [Framing::Tight, Framing::Standard, Framing::Loose];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Framing::Tight => "Tight",
+            Framing::Standard => "Standard",
+            Framing::Loose => "Loose",
+        }
+    }
+
+    /// Padding on each side of the face, as a multiple of its longer side
+    fn padding(&self) -> f64 {
+        match self {
+            Framing::Tight => 0.4,
+            Framing::Standard => 1.1,
+            Framing::Loose => 1.8,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProcessOptions {
+    pub detection: DetectionOptions,
+    pub selection: FaceSelection,
+    /// Extra overrides file, applied on top of the ones next to the images
+    pub overrides_file: Option<PathBuf>,
+}
+
+/// Outcome of processing a single image.
+#[derive(Default)]
+pub struct ImageReport {
+    pub filename: String,
+    /// Path of the source image as it was found in the inputs
+    pub source: PathBuf,
+    /// Faces that passed verification and were cropped
+    pub face_count: usize,
+    /// Candidates discarded as false positives
+    pub rejected_count: usize,
+    /// Crop files written for this image, in face order
+    pub crops: Vec<PathBuf>,
+    /// Manifest entries recorded for `crops`, in the same order
+    pub entries: Vec<ManifestEntry>,
+    pub timings: StageTimings,
+}
+
+/// Time spent in each processing stage, for one image or a whole run.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StageTimings {
+    /// Reading and decoding the source image
+    pub decode: Duration,
+    /// Grayscale conversion, cascade passes and verification
+    pub detect: Duration,
+    /// Extracting crop regions and updating the manifest
+    pub crop: Duration,
+    /// Encoding and writing the crop files
+    pub encode: Duration,
+}
+
+impl StageTimings {
+    pub fn add(&mut self, other: &StageTimings) {
+        self.decode += other.decode;
+        self.detect += other.detect;
+        self.crop += other.crop;
+        self.encode += other.encode;
+    }
+
+    pub fn total(&self) -> Duration {
+        self.decode + self.detect + self.crop + self.encode
+    }
+
+    /// Share of the total spent in each stage, e.g.
+    /// `decode 41% (12.3s) · detect 52% (15.6s) · ...`.
+    pub fn breakdown(&self) -> String {
+        let total = self.total().as_secs_f64().max(f64::EPSILON);
+        [
+            ("decode", self.decode),
+            ("detect", self.detect),
+            ("crop", self.crop),
+            ("encode", self.encode),
+        ]
+        .iter()
+        .map(|(stage, time)| {
+            format!(
+                "{} {:.0}% ({:.1}s)",
+                stage,
+                time.as_secs_f64() / total * 100.0,
+                time.as_secs_f64()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" · ")
+    }
+}
+
+/// Formats a duration for progress display: `1h 02m`, `3m 05s` or `12s`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs / 60 % 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Face rectangles found in one image, in full-resolution pixel coordinates.
+pub struct FaceBoxes {
+    /// Detector rectangles of the faces selected for cropping
+    pub faces: Vec<Rect>,
+    /// Padded crop rectangle for each entry in `faces`
+    pub crops: Vec<Rect>,
+    /// Candidates discarded by verification
+    pub rejected: Vec<Rect>,
+}
+
+/// Totals for a whole run.
+#[derive(Default)]
+pub struct RunSummary {
+    pub images: usize,
+    pub faces: usize,
+    pub rejected: usize,
+    pub timings: StageTimings,
+}
+
+impl RunSummary {
+    fn add(&mut self, report: &ImageReport) {
+        self.images += 1;
+        self.faces += report.face_count;
+        self.rejected += report.rejected_count;
+        self.timings.add(&report.timings);
+    }
+}
+
+/// Runs a batch from the command line with a progress bar showing
+/// throughput and ETA, then prints the summary and per-stage timing.
+pub fn process_images(input: &str, output: &str, options: ProcessOptions) -> Result<()> {
+    let inputs = [PathBuf::from(input)];
+    let total = collect_image_files_from(&inputs)?.len();
+
+    let started = Instant::now();
+    let summary = with_progress_bar(total, |tx| {
+        process_images_with_progress(&inputs, output, Some(tx), options, None)
+    })?;
+
+    let elapsed = started.elapsed();
+    let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    println!(
+        "Processed {} images: {} faces cropped, {} candidates rejected",
+        summary.images, summary.faces, summary.rejected
+    );
+    println!(
+        "Took {}: {:.1} images/s, {:.1} faces/s",
+        format_duration(elapsed),
+        summary.images as f64 / secs,
+        summary.faces as f64 / secs
+    );
+    if summary.images > 0 {
+        println!("Time per stage: {}", summary.timings.breakdown());
+    }
+    Ok(())
+}
+
+/// Retries, from the command line, the images in `input` where no face was
+/// found when they were last processed into `output`, then lists which
+/// were recovered and which are still empty.
+pub fn retry_images_without_faces(
     input: &str,
     output: &str,
-    progress_sender: Option<Sender<ProcessMessage>>,
-    min_neighbors: i32,
-    min_face_size: i32,
+    options: ProcessOptions,
 ) -> Result<()> {
-    let input_path = Path::new(input);
+    let inputs = [PathBuf::from(input)];
+    let runs = manifest::load_runs(Path::new(output));
+    let images: Vec<PathBuf> = collect_image_files_from(&inputs)?
+        .into_iter()
+        .filter(|path| {
+            let source = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            runs.get(&source) == Some(&0)
+        })
+        .collect();
+    if images.is_empty() {
+        println!("No processed image in {} is without faces", input);
+        return Ok(());
+    }
+
+    let summary = with_progress_bar(images.len(), |tx| {
+        retry_without_faces(&images, output, Some(tx), options, None)
+    })?;
+
+    println!(
+        "Recovered {} of {} images without faces",
+        summary.recovered.len(),
+        images.len()
+    );
+    for (path, step) in &summary.recovered {
+        println!("  {} ({})", path.display(), step.label());
+    }
+    if !summary.still_empty.is_empty() {
+        println!("Still without faces:");
+        for path in &summary.still_empty {
+            println!("  {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Runs `work` on a worker thread, showing its progress messages on a
+/// progress bar over `total` images and printing warnings and errors
+/// above it.
+fn with_progress_bar<T: Send>(
+    total: usize,
+    work: impl FnOnce(Sender<ProcessMessage>) -> Result<T> + Send,
+) -> Result<T> {
+    let progress = ProgressBar::new(total as u64);
+    progress.set_style(
+        ProgressStyle::with_template("{bar:40} {pos}/{len} images · {per_sec} · ETA {eta} · {msg}")
+            .expect("valid progress template"),
+    );
+
+    let started = Instant::now();
+    let (tx, rx) = channel();
+    let result = thread::scope(|scope| {
+        let worker = scope.spawn(move || work(tx));
+
+        let mut faces = 0;
+        for message in rx {
+            match message {
+                ProcessMessage::Progress(report) => {
+                    faces += report.face_count;
+                    progress.inc(1);
+                    progress.set_message(format!(
+                        "{} faces ({:.1}/s)",
+                        faces,
+                        faces as f64 / started.elapsed().as_secs_f64()
+                    ));
+                }
+                ProcessMessage::Log(entry) if entry.level != LogLevel::Info => {
+                    progress.println(entry.summary());
+                }
+                _ => {}
+            }
+        }
+        worker.join().expect("processing thread panicked")
+    });
+    progress.finish_and_clear();
+    result
+}
+
+/// Runs a batch on a new thread, sending an `Error` when it fails or
+/// panics so whoever waits for its final message is never left waiting.
+pub fn spawn_run<T>(
+    tx: Sender<ProcessMessage>,
+    work: impl FnOnce(Sender<ProcessMessage>) -> Result<T> + Send + 'static,
+) {
+    thread::spawn(move || {
+        let error = match panic::catch_unwind(AssertUnwindSafe(|| work(tx.clone()))) {
+            Ok(Ok(_)) => return,
+            Ok(Err(e)) => e.to_string(),
+            Err(panic) => {
+                let reason = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                format!("Processing stopped unexpectedly: {}", reason)
+            }
+        };
+        tx.send(ProcessMessage::Error(error)).unwrap_or_default();
+    });
+}
+
+/// Processes every image found in `inputs`, which may mix folders and
+/// individual image files.
+pub fn process_images_with_progress(
+    inputs: &[PathBuf],
+    output: &str,
+    progress_sender: Option<Sender<ProcessMessage>>,
+    options: ProcessOptions,
+    control: Option<ProcessControl>,
+) -> Result<RunSummary> {
     let dst_dir = output;
 
     // Create output directory if it doesn't exist
-    if !Path::new(dst_dir).exists() {
-        fs::create_dir(dst_dir).expect("Failed to create output directory");
-    }
+    fs::create_dir_all(dst_dir).map_err(|e| {
+        opencv::Error::new(
+            opencv::core::StsError,
+            format!("Could not create output folder {}: {}", dst_dir, e),
+        )
+    })?;
+
+    let log = |level, path: Option<&Path>, message: String| {
+        send_log(
+            progress_sender.as_ref(),
+            LogEntry::new(level, path, message),
+        );
+    };
 
-    // Initialize the Haar cascade classifier
-    let mut face_cascade = CascadeClassifier::new("haarcascade_frontalface_default.xml")
-        .expect("Failed to load cascade classifier");
+    // Initialize the Haar cascade classifiers
+    let mut detector = match FaceDetector::new(options.detection.clone()) {
+        Ok(detector) => detector,
+        Err(e) => {
+            log(
+                LogLevel::Error,
+                None,
+                format!("Could not load face detector: {}", e),
+            );
+            return Err(e);
+        }
+    };
 
     // Collect image files
-    let entries = collect_image_files(input_path)?;
+    let entries = collect_image_files_from(inputs)?;
     if entries.is_empty() {
         let error = "No valid image files found.";
+        log(LogLevel::Error, None, error.to_string());
         if let Some(sender) = &progress_sender {
             sender
                 .send(ProcessMessage::Error(error.to_string()))
                 .unwrap_or_default();
-        } else {
-            eprintln!("{}", error);
         }
-        return Ok(());
+        return Ok(RunSummary::default());
     }
+    log(
+        LogLevel::Info,
+        None,
+        format!("Processing {} images into {}", entries.len(), dst_dir),
+    );
+
+    let overrides = load_overrides(&entries, &options, &log);
+    let stems = crop_stems(&entries, Path::new(dst_dir));
 
     // Process each image
-    for path in entries {
-        if let Err(e) = process_single_image(
+    let mut summary = RunSummary::default();
+    for (completed, path) in entries.into_iter().enumerate() {
+        if let Some(control) = &control {
+            if !control.wait_while_paused() {
+                log(
+                    LogLevel::Warning,
+                    None,
+                    format!("Cancelled after {} images", completed),
+                );
+                if let Some(sender) = &progress_sender {
+                    sender
+                        .send(ProcessMessage::Cancelled(completed))
+                        .unwrap_or_default();
+                }
+                return Ok(summary);
+            }
+        }
+
+        let image_options = match overrides.get(&path) {
+            Some(image_overrides) => {
+                let applied = image_overrides.apply(&options);
+                match applied.detection.check_face_sizes() {
+                    Ok(()) => {
+                        log(
+                            LogLevel::Info,
+                            Some(&path),
+                            format!("Using overrides: {}", image_overrides),
+                        );
+                        applied
+                    }
+                    Err(e) => {
+                        log(
+                            LogLevel::Warning,
+                            Some(&path),
+                            format!("Ignored overrides: {}", e),
+                        );
+                        options.clone()
+                    }
+                }
+            }
+            None => options.clone(),
+        };
+        detector.set_limits(&image_options.detection);
+
+        match process_single_image(
             &path,
+            &stems[&path],
             dst_dir,
-            &mut face_cascade,
-            &progress_sender,
-            min_neighbors,
-            min_face_size,
+            &mut detector,
+            image_options.selection,
         ) {
-            let error_msg = format!("Error processing {}: {}", path.display(), e);
-            if let Some(sender) = &progress_sender {
-                sender
-                    .send(ProcessMessage::Error(error_msg))
-                    .unwrap_or_default();
-            } else {
-                eprintln!("{}", error_msg);
+            Ok(Some(report)) => {
+                record_run(Path::new(dst_dir), &report, &log);
+                if report.face_count == 0 {
+                    log(LogLevel::Warning, Some(&path), "No faces found".to_string());
+                } else {
+                    log(
+                        LogLevel::Info,
+                        Some(&path),
+                        format!(
+                            "{} faces cropped, {} candidates rejected",
+                            report.face_count, report.rejected_count
+                        ),
+                    );
+                }
+                summary.add(&report);
+                if let Some(sender) = &progress_sender {
+                    sender
+                        .send(ProcessMessage::Progress(report))
+                        .unwrap_or_default();
+                }
+            }
+            Ok(None) => {
+                log(
+                    LogLevel::Warning,
+                    Some(&path),
+                    "Could not read image, skipped".to_string(),
+                );
+            }
+            Err(e) => {
+                log(LogLevel::Error, Some(&path), e.to_string());
+                if let Some(sender) = &progress_sender {
+                    let error_msg = format!("Error processing {}: {}", path.display(), e);
+                    sender
+                        .send(ProcessMessage::Error(error_msg))
+                        .unwrap_or_default();
+                }
+                return Err(e);
             }
-            return Err(e);
         }
     }
 
+    log(
+        LogLevel::Info,
+        None,
+        format!(
+            "Finished: {} faces from {} images, {} candidates rejected",
+            summary.faces, summary.images, summary.rejected
+        ),
+    );
     if let Some(sender) = progress_sender {
         sender.send(ProcessMessage::Complete).unwrap_or_default();
     }
 
-    Ok(())
+    Ok(summary)
+}
+
+/// Loads the per-image overrides from the input folders, then the extra
+/// file in `options`, logging lines that could not be used.
+fn load_overrides(
+    images: &[PathBuf],
+    options: &ProcessOptions,
+    log: &impl Fn(LogLevel, Option<&Path>, String),
+) -> OverrideTable {
+    let (mut overrides, mut problems) = OverrideTable::for_images(images);
+    if let Some(file) = &options.overrides_file {
+        problems.extend(overrides.load_file(file));
+    }
+    for problem in problems {
+        log(
+            LogLevel::Warning,
+            None,
+            format!("Skipped override: {}", problem),
+        );
+    }
+    if !overrides.is_empty() {
+        log(
+            LogLevel::Info,
+            None,
+            format!("Loaded overrides for {} images", overrides.len()),
+        );
+    }
+    overrides
+}
+
+/// Records how many faces were found in the report's source, so
+/// `--retry-zero` can tell images without faces from unprocessed ones.
+fn record_run(
+    dst_dir: &Path,
+    report: &ImageReport,
+    log: &impl Fn(LogLevel, Option<&Path>, String),
+) {
+    let source = fs::canonicalize(&report.source).unwrap_or_else(|_| report.source.clone());
+    if let Err(e) = manifest::record_run(dst_dir, &source, report.face_count) {
+        log(
+            LogLevel::Warning,
+            Some(&report.source),
+            format!("Could not record the run: {}", e),
+        );
+    }
+}
+
+/// A way of loosening detection for images where no face was found. The
+/// steps are tried in the order of [`RetryStep::LADDER`], each keeping the
+/// changes of the ones before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryStep {
+    FewerNeighbors,
+    SmallerFaces,
+    MoreDetectors,
+    NoVerification,
+}
+
+impl RetryStep {
+    pub const LADDER: [RetryStep; 4] = [
+        RetryStep::FewerNeighbors,
+        RetryStep::SmallerFaces,
+        RetryStep::MoreDetectors,
+        RetryStep::NoVerification,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RetryStep::FewerNeighbors => "fewer neighbors",
+            RetryStep::SmallerFaces => "smaller faces",
+            RetryStep::MoreDetectors => "profile and tilted detectors",
+            RetryStep::NoVerification => "full resolution without verification",
+        }
+    }
+
+    fn loosen(&self, options: &mut ProcessOptions) {
+        let detection = &mut options.detection;
+        match self {
+            RetryStep::FewerNeighbors => {
+                detection.min_neighbors = (detection.min_neighbors / 2).max(1);
+            }
+            RetryStep::SmallerFaces => {
+                detection.min_face_size = match detection.min_face_size {
+                    FaceSize::Pixels(px) => FaceSize::Pixels((px / 3).max(24).min(px)),
+                    FaceSize::Percent(pct) => FaceSize::Percent((pct / 3.0).max(1.0).min(pct)),
+                };
+                detection.max_face_size = None;
+            }
+            RetryStep::MoreDetectors => {
+                detection.profile = true;
+                if detection.rotation_angles.is_empty() {
+                    detection.rotation_angles = vec![15.0, 30.0];
+                }
+            }
+            RetryStep::NoVerification => {
+                detection.detect_max_dimension = None;
+                detection.verify_eyes = false;
+                detection.min_skin_ratio = None;
+            }
+        }
+    }
+}
+
+/// Outcome of retrying images where no face was found.
+#[derive(Debug, Default)]
+pub struct RetrySummary {
+    /// Images where faces were found, with the step that found them
+    pub recovered: Vec<(PathBuf, RetryStep)>,
+    /// Images still without faces after every step, or that failed
+    pub still_empty: Vec<PathBuf>,
+}
+
+/// Processes `images` again, climbing the retry ladder for the ones that
+/// still have no faces. Each image is reported once: when faces are found,
+/// when it fails, when the ladder is exhausted or when the run is cancelled.
+pub fn retry_without_faces(
+    images: &[PathBuf],
+    output: &str,
+    progress_sender: Option<Sender<ProcessMessage>>,
+    options: ProcessOptions,
+    control: Option<ProcessControl>,
+) -> Result<RetrySummary> {
+    let dst_dir = output;
+    fs::create_dir_all(dst_dir).map_err(|e| {
+        opencv::Error::new(
+            opencv::core::StsError,
+            format!("Could not create output folder {}: {}", dst_dir, e),
+        )
+    })?;
+
+    let log = |level, path: Option<&Path>, message: String| {
+        send_log(
+            progress_sender.as_ref(),
+            LogEntry::new(level, path, message),
+        );
+    };
+    let send = |message: ProcessMessage| {
+        if let Some(sender) = &progress_sender {
+            sender.send(message).unwrap_or_default();
+        }
+    };
+    // Reports an image that ends the retry without faces, so the GUI keeps
+    // it in the list and the progress bar still reaches the total
+    let send_empty = |path: &Path, last_report: Option<ImageReport>| {
+        send(ProcessMessage::Progress(last_report.unwrap_or_else(|| {
+            ImageReport {
+                filename: path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                source: path.to_path_buf(),
+                ..Default::default()
+            }
+        })));
+    };
+
+    log(
+        LogLevel::Info,
+        None,
+        format!("Retrying {} images without faces", images.len()),
+    );
+    let overrides = load_overrides(images, &options, &log);
+    let stems = crop_stems(images, Path::new(dst_dir));
+
+    let mut summary = RetrySummary::default();
+    let mut remaining: Vec<(PathBuf, Option<ImageReport>)> =
+        images.iter().map(|path| (path.clone(), None)).collect();
+    let mut current = options.clone();
+    let mut applied = Vec::new();
+    let mut completed = 0;
+
+    for step in RetryStep::LADDER {
+        if remaining.is_empty() {
+            break;
+        }
+
+        // A step whose cascades are missing is skipped, not kept for later
+        let mut candidate = current.clone();
+        step.loosen(&mut candidate);
+        let mut detector = match FaceDetector::new(candidate.detection.clone()) {
+            Ok(detector) => detector,
+            Err(e) => {
+                log(
+                    LogLevel::Warning,
+                    None,
+                    format!("Skipped retry with {}: {}", step.label(), e),
+                );
+                continue;
+            }
+        };
+        current = candidate;
+        applied.push(step);
+        log(
+            LogLevel::Info,
+            None,
+            format!("Trying {} on {} images", step.label(), remaining.len()),
+        );
+
+        let mut still_empty = Vec::new();
+        let mut queue = remaining.into_iter();
+        while let Some((path, last_report)) = queue.next() {
+            if let Some(control) = &control {
+                if !control.wait_while_paused() {
+                    // Images not tried yet keep their place in the list
+                    let untried = still_empty
+                        .into_iter()
+                        .chain([(path, last_report)])
+                        .chain(queue);
+                    for (path, last_report) in untried {
+                        send_empty(&path, last_report);
+                        summary.still_empty.push(path);
+                    }
+                    log(
+                        LogLevel::Warning,
+                        None,
+                        format!("Cancelled after {} images", completed),
+                    );
+                    send(ProcessMessage::Cancelled(completed));
+                    return Ok(summary);
+                }
+            }
+
+            // The image's own overrides, loosened by every step so far
+            let mut image_options = overrides
+                .get(&path)
+                .map(|image_overrides| image_overrides.apply(&options))
+                .filter(|applied| applied.detection.check_face_sizes().is_ok())
+                .unwrap_or_else(|| options.clone());
+            for applied_step in &applied {
+                applied_step.loosen(&mut image_options);
+            }
+            detector.set_limits(&image_options.detection);
+
+            let result = process_single_image(
+                &path,
+                &stems[&path],
+                dst_dir,
+                &mut detector,
+                image_options.selection,
+            );
+            if let Ok(Some(report)) = &result {
+                record_run(Path::new(dst_dir), report, &log);
+            }
+            match result {
+                Ok(Some(report)) if report.face_count > 0 => {
+                    log(
+                        LogLevel::Info,
+                        Some(&path),
+                        format!("{} faces found with {}", report.face_count, step.label()),
+                    );
+                    completed += 1;
+                    summary.recovered.push((path, step));
+                    send(ProcessMessage::Progress(report));
+                }
+                Ok(Some(report)) => still_empty.push((path, Some(report))),
+                Ok(None) => {
+                    log(
+                        LogLevel::Warning,
+                        Some(&path),
+                        "Could not read image, skipped".to_string(),
+                    );
+                    completed += 1;
+                    send_empty(&path, None);
+                    summary.still_empty.push(path);
+                }
+                Err(e) => {
+                    log(LogLevel::Error, Some(&path), e.to_string());
+                    completed += 1;
+                    send_empty(&path, None);
+                    summary.still_empty.push(path);
+                }
+            }
+        }
+        remaining = still_empty;
+    }
+
+    for (path, last_report) in remaining {
+        log(
+            LogLevel::Warning,
+            Some(&path),
+            "Still no faces after every retry step".to_string(),
+        );
+        send_empty(&path, last_report);
+        summary.still_empty.push(path);
+    }
+
+    log(
+        LogLevel::Info,
+        None,
+        format!(
+            "Finished retry: {} images recovered, {} still without faces",
+            summary.recovered.len(),
+            summary.still_empty.len()
+        ),
+    );
+    send(ProcessMessage::Complete);
+    Ok(summary)
+}
+
+/// Sends a run log event to the GUI. Without a GUI, warnings and errors are
+/// printed to stderr and info events are left to the run summary.
+fn send_log(sender: Option<&Sender<ProcessMessage>>, entry: LogEntry) {
+    match sender {
+        Some(sender) => sender.send(ProcessMessage::Log(entry)).unwrap_or_default(),
+        None if entry.level != LogLevel::Info => eprintln!("{}", entry.summary()),
+        None => {}
+    }
 }
 
 pub fn collect_image_files(input_path: &Path) -> Result<Vec<PathBuf>> {
@@ -91,7 +947,7 @@ pub fn collect_image_files(input_path: &Path) -> Result<Vec<PathBuf>> {
         }
     } else if input_path.is_dir() {
         entries = fs::read_dir(input_path)
-            .expect("Input directory not found")
+            .map_err(io_error)?
             .filter_map(|entry| {
                 if let Ok(entry) = entry {
                     let path = entry.path();
@@ -107,9 +963,91 @@ pub fn collect_image_files(input_path: &Path) -> Result<Vec<PathBuf>> {
     Ok(entries)
 }
 
-fn is_valid_image(path: &Path) -> bool {
+/// Collects the images of several folders and files, skipping duplicates
+/// so an image dropped alongside its folder is only processed once.
+pub fn collect_image_files_from(inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for input in inputs {
+        for path in collect_image_files(input)? {
+            if seen.insert(path.clone()) {
+                entries.push(path);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Base names for the crops of `images` in `dst_dir`. An image keeps the
+/// name its crops already have in the manifest; otherwise it gets its file
+/// stem, unless another image in the batch or the manifest has the same
+/// one. Those get their folder name added (`{folder}_{stem}`) and then a
+/// counter while that is still taken, so no image overwrites another's
+/// crops.
+pub fn crop_stems(images: &[PathBuf], dst_dir: &Path) -> HashMap<PathBuf, String> {
+    let file_stem = |path: &Path| {
+        path.file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    };
+
+    // Stems already in the output, and the source that owns each
+    let mut owners: HashMap<String, PathBuf> = HashMap::new();
+    let mut existing: HashMap<PathBuf, String> = HashMap::new();
+    for entry in manifest::load(dst_dir).into_values() {
+        if let Some((stem, _)) = entry.crop.rsplit_once("_face_") {
+            owners.insert(stem.to_lowercase(), entry.source.clone());
+            existing.insert(entry.source, stem.to_string());
+        }
+    }
+
+    let mut stems = HashMap::new();
+    let mut groups: HashMap<String, Vec<(&PathBuf, PathBuf)>> = HashMap::new();
+    for path in images {
+        let source = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        match existing.get(&source) {
+            Some(stem) => {
+                stems.insert(path.clone(), stem.clone());
+            }
+            None => groups
+                .entry(file_stem(path).to_lowercase())
+                .or_default()
+                .push((path, source)),
+        }
+    }
+
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort();
+    for (key, mut group) in groups {
+        let clashes = group.len() > 1 || owners.contains_key(&key);
+        group.sort();
+        for (path, source) in group {
+            let base = match path.parent().and_then(Path::file_name) {
+                Some(folder) if clashes => {
+                    format!("{}_{}", folder.to_string_lossy(), file_stem(path))
+                }
+                _ => file_stem(path),
+            };
+            let mut stem = base.clone();
+            let mut n = 2;
+            while owners
+                .get(&stem.to_lowercase())
+                .is_some_and(|owner| *owner != source)
+            {
+                stem = format!("{}_{}", base, n);
+                n += 1;
+            }
+            owners.insert(stem.to_lowercase(), source);
+            stems.insert(path.clone(), stem);
+        }
+    }
+    stems
+}
+
+pub fn is_valid_image(path: &Path) -> bool {
     if let Some(ext) = path.extension() {
-        let ext = ext.to_str().unwrap().to_lowercase();
+        let ext = ext.to_string_lossy().to_lowercase();
         return ext == "png" || ext == "jpg" || ext == "jpeg";
     }
     false
@@ -117,73 +1055,225 @@ fn is_valid_image(path: &Path) -> bool {
 
 fn process_single_image(
     path: &Path,
+    crop_stem: &str,
     dst_dir: &str,
-    face_cascade: &mut CascadeClassifier,
-    progress_sender: &Option<Sender<ProcessMessage>>,
-    min_neighbors: i32,
-    min_face_size: i32,
-) -> Result<()> {
-    let filename = path.file_name().unwrap().to_str().unwrap();
-
-    // Split filename and extension
-    let stem = path.file_stem().unwrap().to_str().unwrap();
-    let ext = path.extension().unwrap().to_str().unwrap();
+    detector: &mut FaceDetector,
+    selection: FaceSelection,
+) -> Result<Option<ImageReport>> {
+    let filename = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut timings = StageTimings::default();
 
     // Load and process image
-    let image = imgcodecs::imread(path.to_str().unwrap(), imgcodecs::IMREAD_COLOR)?;
+    let started = Instant::now();
+    let image = imgcodecs::imread(&path.to_string_lossy(), imgcodecs::IMREAD_COLOR)?;
+    timings.decode = started.elapsed();
     if image.empty() {
-        return Ok(());
+        return Ok(None);
     }
 
+    let started = Instant::now();
+    let boxes = detect_faces(&image, detector, selection)?;
+    timings.detect = started.elapsed();
+
+    let (crops, entries) = write_crops(
+        &image,
+        path,
+        crop_stem,
+        &boxes.faces,
+        &boxes.crops,
+        Path::new(dst_dir),
+        &mut timings,
+    )?;
+
+    Ok(Some(ImageReport {
+        filename: filename.to_string(),
+        source: path.to_path_buf(),
+        face_count: boxes.faces.len(),
+        rejected_count: boxes.rejected.len(),
+        crops,
+        entries,
+        timings,
+    }))
+}
+
+/// Runs detection and face selection on a loaded color image and computes
+/// the crop rectangle for each selected face, without writing anything.
+pub fn detect_faces(
+    image: &Mat,
+    detector: &mut FaceDetector,
+    selection: FaceSelection,
+) -> Result<FaceBoxes> {
     // Convert to grayscale
     let mut gray = Mat::default();
     imgproc::cvt_color(
-        &image,
+        image,
         &mut gray,
         imgproc::COLOR_BGR2GRAY,
         0,
         AlgorithmHint::ALGO_HINT_DEFAULT,
     )?;
 
-    // Detect faces
-    let mut faces: Vector<Rect> = Vector::new();
-    face_cascade.detect_multi_scale(
-        &gray,
-        &mut faces,
-        1.4,
-        min_neighbors,
-        0,
-        Size {
-            width: min_face_size,
-            height: min_face_size,
-        },
-        Size::default(),
-    )?;
+    // Detect faces across all enabled detector passes
+    let detections = detector.detect(image, &gray)?;
+    let faces = selection.apply(detections.faces, image);
+    let crops = faces
+        .iter()
+        .map(|face| calculate_padded_rect(face, image, Framing::Standard))
+        .collect();
+
+    Ok(FaceBoxes {
+        faces,
+        crops,
+        rejected: detections.rejected,
+    })
+}
 
-    let face_count = faces.len();
+/// Crops and saves each rectangle as `{crop_stem}_face_{n}.{ext}` in
+/// `dst_dir`, recording in the manifest which source and face each crop
+/// came from. Time spent is added to the crop and encode stages of
+/// `timings`. Returns the paths of the written crops and their manifest
+/// entries.
+fn write_crops(
+    image: &Mat,
+    source: &Path,
+    crop_stem: &str,
+    faces: &[Rect],
+    crops: &[Rect],
+    dst_dir: &Path,
+    timings: &mut StageTimings,
+) -> Result<(Vec<PathBuf>, Vec<ManifestEntry>)> {
+    let started = Instant::now();
+    let mut encode = Duration::ZERO;
+    let ext = source.extension().unwrap_or_default().to_string_lossy();
+    let source = fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf());
 
-    if let Some(sender) = progress_sender {
-        sender
-            .send(ProcessMessage::Progress(filename.to_string(), face_count))
-            .unwrap_or_default();
+    let mut entries = Vec::new();
+    let mut written = Vec::new();
+    for (face_idx, (face, rect)) in faces.iter().zip(crops).enumerate() {
+        let face_clip = Mat::roi(image, *rect)?;
+        let crop_name = format!("{}_face_{}.{}", crop_stem, face_idx + 1, ext);
+        let face_filename = dst_dir.join(&crop_name);
+        let encode_started = Instant::now();
+        imgcodecs::imwrite(
+            &face_filename.to_string_lossy(),
+            &face_clip,
+            &Vector::<i32>::new(),
+        )?;
+        encode += encode_started.elapsed();
+        written.push(face_filename);
+
+        entries.push(ManifestEntry {
+            crop: crop_name,
+            source: source.clone(),
+            crop_rect: *rect,
+            face_rect: *face,
+        });
+    }
+
+    let result = manifest::append(dst_dir, &entries).map_err(io_error);
+    // Everything but the file writes counts as cropping
+    timings.encode += encode;
+    timings.crop += started.elapsed().saturating_sub(encode);
+    result.map(|()| (written, entries))
+}
+
+/// Re-exports the crops of a single source image from manually edited face
+/// boxes, replacing any crops previously written for it. Returns the number
+/// of crops written.
+pub fn export_crops(path: &Path, dst_dir: &Path, faces: &[Rect]) -> Result<usize> {
+    let stem = crop_stems(&[path.to_path_buf()], dst_dir)
+        .remove(path)
+        .unwrap_or_default();
+    let ext = path.extension().unwrap_or_default().to_string_lossy();
+
+    let image = imgcodecs::imread(&path.to_string_lossy(), imgcodecs::IMREAD_COLOR)?;
+    if image.empty() {
+        return Err(opencv::Error::new(
+            opencv::core::StsError,
+            format!("Could not read {}", path.display()),
+        ));
     }
 
-    // Process all detected faces
-    for face_idx in 0..face_count {
-        let face = faces.get(face_idx)?;
-        let rect = calculate_padded_rect(&face, &image);
+    fs::create_dir_all(dst_dir).map_err(io_error)?;
 
-        // Crop and save the face
-        let face_clip = Mat::roi(&image, rect)?;
-        let face_filename = format!("{}/{}_face_{}.{}", dst_dir, stem, face_idx + 1, ext);
-        imgcodecs::imwrite(&face_filename, &face_clip, &Vector::<i32>::new())?;
+    // Remove the old crops so a deleted box doesn't leave a stale file behind
+    if let Ok(entries) = fs::read_dir(dst_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            if is_crop_of(&name.to_string_lossy(), &stem, &ext) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
     }
 
-    Ok(())
+    let crops: Vec<Rect> = faces
+        .iter()
+        .map(|face| calculate_padded_rect(face, &image, Framing::Standard))
+        .collect();
+    write_crops(
+        &image,
+        path,
+        &stem,
+        faces,
+        &crops,
+        dst_dir,
+        &mut StageTimings::default(),
+    )?;
+
+    Ok(crops.len())
+}
+
+/// Rewrites the crop at `path` from the face box in its manifest entry with
+/// a different framing, and records the new crop area in the manifest in
+/// `manifest_dir`. Returns the updated entry.
+pub fn recrop(
+    entry: &ManifestEntry,
+    path: &Path,
+    manifest_dir: &Path,
+    framing: Framing,
+) -> Result<ManifestEntry> {
+    let image = imgcodecs::imread(&entry.source.to_string_lossy(), imgcodecs::IMREAD_COLOR)?;
+    if image.empty() {
+        return Err(opencv::Error::new(
+            opencv::core::StsError,
+            format!("Could not read {}", entry.source.display()),
+        ));
+    }
+
+    let rect = calculate_padded_rect(&entry.face_rect, &image, framing);
+    let face_clip = Mat::roi(&image, rect)?;
+    imgcodecs::imwrite(&path.to_string_lossy(), &face_clip, &Vector::<i32>::new())?;
+
+    let entry = ManifestEntry {
+        crop_rect: rect,
+        ..entry.clone()
+    };
+    manifest::append(manifest_dir, std::slice::from_ref(&entry)).map_err(io_error)?;
+    Ok(entry)
+}
+
+fn io_error(e: io::Error) -> opencv::Error {
+    opencv::Error::new(opencv::core::StsError, e.to_string())
+}
+
+/// Whether `file_name` is a crop written for the source `{stem}.{ext}`.
+fn is_crop_of(file_name: &str, stem: &str, ext: &str) -> bool {
+    let Some(rest) = file_name
+        .strip_prefix(stem)
+        .and_then(|rest| rest.strip_prefix("_face_"))
+    else {
+        return false;
+    };
+    match rest.split_once('.') {
+        Some((index, crop_ext)) => {
+            !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()) && crop_ext == ext
+        }
+        None => false,
+    }
 }
 
-fn calculate_padded_rect(face: &Rect, image: &Mat) -> Rect {
-    let padding = ((face.width.max(face.height)) as f64 * 1.1).round() as i32;
+fn calculate_padded_rect(face: &Rect, image: &Mat, framing: Framing) -> Rect {
+    let padding = ((face.width.max(face.height)) as f64 * framing.padding()).round() as i32;
 
     let padded_top = (face.y - padding).max(0);
     let padded_left = (face.x - padding).max(0);
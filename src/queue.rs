@@ -0,0 +1,358 @@
+use crate::processor::{self, ProcessControl, ProcessMessage};
+use crate::settings::DetectionParams;
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, channel};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Cancelled,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "Queued",
+            JobStatus::Running => "Running",
+            JobStatus::Done => "Done",
+            JobStatus::Cancelled => "Cancelled",
+            JobStatus::Failed => "Failed",
+        }
+    }
+
+    fn color(&self) -> egui::Color32 {
+        match self {
+            JobStatus::Queued => egui::Color32::GRAY,
+            JobStatus::Running => egui::Color32::LIGHT_BLUE,
+            JobStatus::Done => egui::Color32::GREEN,
+            JobStatus::Cancelled => egui::Color32::YELLOW,
+            JobStatus::Failed => egui::Color32::RED,
+        }
+    }
+}
+
+/// One input/output pair with the parameters it was queued with.
+pub struct Job {
+    id: usize,
+    inputs: Vec<PathBuf>,
+    output: PathBuf,
+    params: DetectionParams,
+    status: JobStatus,
+    total_images: usize,
+    processed_images: usize,
+    total_faces: usize,
+    total_rejected: usize,
+    log: Vec<String>,
+}
+
+impl Job {
+    fn name(&self) -> String {
+        let input = match self.inputs.as_slice() {
+            [single] => single.display().to_string(),
+            inputs => format!("{} inputs", inputs.len()),
+        };
+        format!("{} → {}", input, self.output.display())
+    }
+
+    fn progress(&self) -> f32 {
+        if self.total_images > 0 {
+            self.processed_images as f32 / self.total_images as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Clears the results of an earlier run so the job can run again.
+    fn reset(&mut self) {
+        self.status = JobStatus::Queued;
+        self.processed_images = 0;
+        self.total_faces = 0;
+        self.total_rejected = 0;
+        self.log.push("Queued for retry".to_string());
+    }
+}
+
+/// The job currently being processed in the background.
+struct RunningJob {
+    id: usize,
+    rx: Receiver<ProcessMessage>,
+    control: ProcessControl,
+}
+
+/// Folder pairs processed one after another in the background, each with
+/// its own parameters, status, progress and log.
+pub struct JobQueue {
+    jobs: Vec<Job>,
+    next_id: usize,
+    running: Option<RunningJob>,
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self {
+            jobs: Vec::new(),
+            next_id: 1,
+            running: None,
+        }
+    }
+
+    pub fn enqueue(&mut self, inputs: Vec<PathBuf>, output: PathBuf, params: DetectionParams) {
+        let total_images = processor::collect_image_files_from(&inputs)
+            .map(|files| files.len())
+            .unwrap_or_default();
+        self.jobs.push(Job {
+            id: self.next_id,
+            inputs,
+            output,
+            params,
+            status: JobStatus::Queued,
+            total_images,
+            processed_images: 0,
+            total_faces: 0,
+            total_rejected: 0,
+            log: vec![format!("Queued with {} images", total_images)],
+        });
+        self.next_id += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    fn job_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    /// Collects progress from the running job and, if `can_start`, starts
+    /// the next queued one once it has finished.
+    pub fn update(&mut self, ctx: &egui::Context, can_start: bool) {
+        let polled = self
+            .running
+            .as_ref()
+            .map(|running| (running.id, running.rx.try_iter().collect::<Vec<_>>()));
+        if let Some((id, messages)) = polled {
+            let mut finished = false;
+            if let Some(job) = self.job_mut(id) {
+                for message in messages {
+                    finished |= apply_message(job, message);
+                }
+            }
+            if finished {
+                self.running = None;
+            }
+        }
+
+        if self.running.is_none() && can_start {
+            self.start_next(ctx);
+        }
+    }
+
+    fn start_next(&mut self, ctx: &egui::Context) {
+        let Some(job) = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.status == JobStatus::Queued)
+        else {
+            return;
+        };
+
+        let (tx, rx) = channel();
+        let control = ProcessControl::default();
+        let inputs = job.inputs.clone();
+        let output = job.output.to_string_lossy().to_string();
+        let options = job.params.process_options();
+        job.status = JobStatus::Running;
+        job.total_images = processor::collect_image_files_from(&inputs)
+            .map(|files| files.len())
+            .unwrap_or(job.total_images);
+        job.log
+            .push(format!("Started: {} images", job.total_images));
+        self.running = Some(RunningJob {
+            id: job.id,
+            rx,
+            control: control.clone(),
+        });
+
+        let ctx = ctx.clone();
+        processor::spawn_run(tx, move |tx| {
+            let result = processor::process_images_with_progress(
+                &inputs,
+                &output,
+                Some(tx),
+                options,
+                Some(control),
+            );
+            ctx.request_repaint();
+            result
+        });
+    }
+
+    fn cancel(&mut self, id: usize) {
+        match &self.running {
+            Some(running) if running.id == id => running.control.cancel(),
+            _ => {
+                if let Some(job) = self.job_mut(id) {
+                    job.status = JobStatus::Cancelled;
+                    job.log.push("Cancelled before it started".to_string());
+                }
+            }
+        }
+    }
+
+    /// Shows the queue window. Returns `false` once the user closes it.
+    pub fn show(&mut self, ctx: &egui::Context) -> bool {
+        let mut queue_open = true;
+        let mut retry = None;
+        let mut cancel = None;
+        let mut remove = None;
+
+        egui::Window::new("Job Queue")
+            .resizable(true)
+            .default_size([600.0, 400.0])
+            .open(&mut queue_open)
+            .show(ctx, |ui| {
+                if self.jobs.is_empty() {
+                    ui.centered_and_justified(|ui| {
+                        ui.label("No jobs yet. Use \"Add to Queue\" to queue the current folders and parameters.");
+                    });
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    let finished = self
+                        .jobs
+                        .iter()
+                        .filter(|job| !matches!(job.status, JobStatus::Queued | JobStatus::Running))
+                        .count();
+                    ui.label(format!("{} of {} jobs finished", finished, self.jobs.len()));
+                    if ui
+                        .add_enabled(finished > 0, egui::Button::new("Clear Finished"))
+                        .clicked()
+                    {
+                        self.jobs.retain(|job| {
+                            matches!(job.status, JobStatus::Queued | JobStatus::Running)
+                        });
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for job in &self.jobs {
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(job.status.color(), job.status.label());
+                                ui.label(job.name());
+                            });
+                            ui.add(egui::ProgressBar::new(job.progress()).text(format!(
+                                "{} / {} images · {} faces · {} rejected",
+                                job.processed_images,
+                                job.total_images,
+                                job.total_faces,
+                                job.total_rejected
+                            )));
+                            ui.horizontal(|ui| {
+                                match job.status {
+                                    JobStatus::Queued | JobStatus::Running => {
+                                        if ui.button("⏹ Cancel").clicked() {
+                                            cancel = Some(job.id);
+                                        }
+                                    }
+                                    _ => {
+                                        if ui.button("↻ Retry").clicked() {
+                                            retry = Some(job.id);
+                                        }
+                                    }
+                                }
+                                if job.status != JobStatus::Running
+                                    && ui.button("🗑 Remove").clicked()
+                                {
+                                    remove = Some(job.id);
+                                }
+                            });
+                            egui::CollapsingHeader::new("Log")
+                                .id_source(("job_log", job.id))
+                                .show(ui, |ui| {
+                                    egui::ScrollArea::vertical()
+                                        .max_height(150.0)
+                                        .stick_to_bottom(true)
+                                        .show(ui, |ui| {
+                                            for line in &job.log {
+                                                ui.monospace(line);
+                                            }
+                                        });
+                                });
+                        });
+                    }
+                });
+            });
+
+        if let Some(id) = retry {
+            if let Some(job) = self.job_mut(id) {
+                job.reset();
+            }
+        }
+        if let Some(id) = cancel {
+            self.cancel(id);
+        }
+        if let Some(id) = remove {
+            self.jobs.retain(|job| job.id != id);
+        }
+
+        queue_open
+    }
+}
+
+/// Records a progress message against its job. Returns `true` once the run
+/// has ended.
+fn apply_message(job: &mut Job, message: ProcessMessage) -> bool {
+    match message {
+        ProcessMessage::Progress(report) => {
+            job.processed_images += 1;
+            job.total_faces += report.face_count;
+            job.total_rejected += report.rejected_count;
+            false
+        }
+        ProcessMessage::Log(entry) => {
+            let file = entry
+                .path
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map(|name| format!("{}: ", name.to_string_lossy()))
+                .unwrap_or_default();
+            job.log
+                .push(format!("{} {}{}", entry.level.label(), file, entry.message));
+            false
+        }
+        ProcessMessage::Complete => {
+            job.status = JobStatus::Done;
+            job.log.push(format!(
+                "Done: {} faces from {} images",
+                job.total_faces, job.processed_images
+            ));
+            true
+        }
+        ProcessMessage::Cancelled(completed) => {
+            job.status = JobStatus::Cancelled;
+            job.log
+                .push(format!("Cancelled after {} images", completed));
+            true
+        }
+        ProcessMessage::Error(error) => {
+            // A failing image is reported by the processor and again by the
+            // thread that ran it; keep the first, more detailed message
+            if job.status != JobStatus::Failed {
+                job.status = JobStatus::Failed;
+                job.log.push(error);
+            }
+            true
+        }
+    }
+}
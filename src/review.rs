@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File in the output folder that records review decisions
+pub const REVIEW_FILE: &str = ".headshot-review";
+
+/// Subfolder of the output folder that rejected crops are moved into
+pub const REJECTED_DIR: &str = "rejected";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ReviewStatus {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+    Flagged,
+}
+
+impl ReviewStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReviewStatus::Pending => "Pending",
+            ReviewStatus::Approved => "Approved",
+            ReviewStatus::Rejected => "Rejected",
+            ReviewStatus::Flagged => "Flagged",
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Pending => "pending",
+            ReviewStatus::Approved => "approved",
+            ReviewStatus::Rejected => "rejected",
+            ReviewStatus::Flagged => "flagged",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(ReviewStatus::Pending),
+            "approved" => Some(ReviewStatus::Approved),
+            "rejected" => Some(ReviewStatus::Rejected),
+            "flagged" => Some(ReviewStatus::Flagged),
+            _ => None,
+        }
+    }
+}
+
+/// Review decisions for the crops in one output folder, keyed by file name.
+/// Stored as `status<TAB>file name` lines so a review can be resumed.
+pub struct ReviewLog {
+    dir: PathBuf,
+    decisions: HashMap<String, ReviewStatus>,
+}
+
+impl ReviewLog {
+    pub fn load(dir: &Path) -> Self {
+        let mut decisions = HashMap::new();
+        if let Ok(contents) = fs::read_to_string(dir.join(REVIEW_FILE)) {
+            for line in contents.lines() {
+                if let Some((status, name)) = line.split_once('\t') {
+                    if let Some(status) = ReviewStatus::parse(status) {
+                        decisions.insert(name.to_string(), status);
+                    }
+                }
+            }
+        }
+
+        Self {
+            dir: dir.to_path_buf(),
+            decisions,
+        }
+    }
+
+    pub fn get(&self, file_name: &str) -> ReviewStatus {
+        self.decisions.get(file_name).copied().unwrap_or_default()
+    }
+
+    /// Records a decision and writes the log back to disk.
+    pub fn set(&mut self, file_name: &str, status: ReviewStatus) -> io::Result<()> {
+        if status == ReviewStatus::Pending {
+            self.decisions.remove(file_name);
+        } else {
+            self.decisions.insert(file_name.to_string(), status);
+        }
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let mut names: Vec<&String> = self.decisions.keys().collect();
+        names.sort();
+
+        let mut contents = String::new();
+        for name in names {
+            contents.push_str(self.decisions[name].as_str());
+            contents.push('\t');
+            contents.push_str(name);
+            contents.push('\n');
+        }
+        fs::write(self.dir.join(REVIEW_FILE), contents)
+    }
+
+    pub fn rejected_dir(&self) -> PathBuf {
+        self.dir.join(REJECTED_DIR)
+    }
+
+    /// Moves a crop into or out of the rejected folder to match a status
+    /// change, returning its new path.
+    pub fn move_for_status(&self, path: &Path, status: ReviewStatus) -> io::Result<PathBuf> {
+        let file_name = path.file_name().unwrap_or_default();
+        let target_dir = if status == ReviewStatus::Rejected {
+            let rejected_dir = self.rejected_dir();
+            fs::create_dir_all(&rejected_dir)?;
+            rejected_dir
+        } else {
+            self.dir.clone()
+        };
+
+        let target = target_dir.join(file_name);
+        if target != path {
+            fs::rename(path, &target)?;
+        }
+        Ok(target)
+    }
+}
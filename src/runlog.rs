@@ -0,0 +1,242 @@
+use eframe::egui;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl LogLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogLevel::Info => "INFO",
+            LogLevel::Warning => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+
+    fn color(&self) -> egui::Color32 {
+        match self {
+            LogLevel::Info => egui::Color32::GRAY,
+            LogLevel::Warning => egui::Color32::YELLOW,
+            LogLevel::Error => egui::Color32::RED,
+        }
+    }
+}
+
+/// One event of a processing run, optionally about a specific file.
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub level: LogLevel,
+    pub timestamp: SystemTime,
+    pub path: Option<PathBuf>,
+    pub message: String,
+}
+
+impl LogEntry {
+    pub fn new(level: LogLevel, path: Option<&Path>, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            timestamp: SystemTime::now(),
+            path: path.map(Path::to_path_buf),
+            message: message.into(),
+        }
+    }
+
+    /// One-line description for the terminal, e.g. `WARN: a.jpg: No faces found`.
+    pub fn summary(&self) -> String {
+        match &self.path {
+            Some(path) => format!(
+                "{}: {}: {}",
+                self.level.label(),
+                path.display(),
+                self.message
+            ),
+            None => format!("{}: {}", self.level.label(), self.message),
+        }
+    }
+}
+
+impl std::fmt::Display for LogEntry {
+    /// Formats the entry as one tab-separated line for the saved log.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}",
+            format_timestamp(self.timestamp, true),
+            self.level.label(),
+            self.path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+            self.message
+        )
+    }
+}
+
+/// Formats a time as UTC `HH:MM:SS`, prefixed by `YYYY-MM-DD` when
+/// `with_date` is set.
+pub fn format_timestamp(time: SystemTime, with_date: bool) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    let clock = format!(
+        "{:02}:{:02}:{:02}",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    );
+    if !with_date {
+        return clock;
+    }
+
+    let (year, month, day) = civil_date(days as i64);
+    format!("{:04}-{:02}-{:02}T{}Z", year, month, day, clock)
+}
+
+/// Converts days since 1970-01-01 to a `(year, month, day)` civil date
+/// (Howard Hinnant's algorithm).
+pub fn civil_date(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Scrollable list of run events with filtering by level and export to a
+/// text file.
+pub struct LogPanel {
+    entries: Vec<LogEntry>,
+    show_info: bool,
+    show_warnings: bool,
+    show_errors: bool,
+    status: Option<String>,
+}
+
+impl LogPanel {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            show_info: true,
+            show_warnings: true,
+            show_errors: true,
+            status: None,
+        }
+    }
+
+    pub fn push(&mut self, entry: LogEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn count(&self, level: LogLevel) -> usize {
+        self.entries.iter().filter(|e| e.level == level).count()
+    }
+
+    fn is_shown(&self, level: LogLevel) -> bool {
+        match level {
+            LogLevel::Info => self.show_info,
+            LogLevel::Warning => self.show_warnings,
+            LogLevel::Error => self.show_errors,
+        }
+    }
+
+    /// Writes every entry, regardless of the filter, to `path`.
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let mut contents = String::new();
+        for entry in &self.entries {
+            contents.push_str(&entry.to_string());
+            contents.push('\n');
+        }
+        fs::write(path, contents)
+    }
+
+    /// Shows the log window. Returns `false` once the user closes it.
+    pub fn show(&mut self, ctx: &egui::Context) -> bool {
+        let mut log_open = true;
+
+        egui::Window::new("Run Log")
+            .resizable(true)
+            .default_size([700.0, 400.0])
+            .open(&mut log_open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let info = self.count(LogLevel::Info);
+                    let warnings = self.count(LogLevel::Warning);
+                    let errors = self.count(LogLevel::Error);
+                    ui.checkbox(&mut self.show_info, format!("Info ({})", info));
+                    ui.checkbox(&mut self.show_warnings, format!("Warnings ({})", warnings));
+                    ui.checkbox(&mut self.show_errors, format!("Errors ({})", errors));
+                    ui.separator();
+
+                    if ui
+                        .add_enabled(!self.entries.is_empty(), egui::Button::new("💾 Save Log"))
+                        .clicked()
+                    {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .set_title("Save Log")
+                            .set_file_name("headshot-log.txt")
+                            .save_file()
+                        {
+                            self.status = Some(match self.save(&path) {
+                                Ok(()) => format!("Saved log to {}", path.display()),
+                                Err(e) => format!("Could not save log: {}", e),
+                            });
+                        }
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.entries.clear();
+                        self.status = None;
+                    }
+                });
+                if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+                ui.separator();
+
+                let visible: Vec<&LogEntry> = self
+                    .entries
+                    .iter()
+                    .filter(|e| self.is_shown(e.level))
+                    .collect();
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::both()
+                    .auto_shrink([false, false])
+                    .stick_to_bottom(true)
+                    .show_rows(ui, row_height, visible.len(), |ui, rows| {
+                        for entry in &visible[rows] {
+                            ui.horizontal(|ui| {
+                                ui.monospace(format_timestamp(entry.timestamp, false));
+                                ui.colored_label(
+                                    entry.level.color(),
+                                    egui::RichText::new(entry.level.label()).monospace(),
+                                );
+                                if let Some(path) = &entry.path {
+                                    let name = path
+                                        .file_name()
+                                        .unwrap_or_default()
+                                        .to_string_lossy()
+                                        .to_string();
+                                    ui.monospace(name).on_hover_text(path.display().to_string());
+                                }
+                                ui.label(&entry.message);
+                            });
+                        }
+                    });
+            });
+
+        log_open
+    }
+}
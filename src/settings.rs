@@ -0,0 +1,219 @@
+use crate::detector::{DetectionOptions, FaceSize};
+use crate::processor::{FaceSelection, ProcessOptions};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the settings file inside the per-user config folder
+const SETTINGS_FILE: &str = "settings.conf";
+
+/// Number of folders kept in the recent-folders list
+pub const MAX_RECENT_FOLDERS: usize = 8;
+
+/// Initial size of the main window
+pub const DEFAULT_WINDOW_SIZE: [f32; 2] = [800.0, 300.0];
+
+/// Detection parameters as edited in the GUI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetectionParams {
+    pub min_neighbors: i32,
+    pub min_face_size: FaceSize,
+    pub max_face_size: Option<FaceSize>,
+    pub detect_profile: bool,
+    pub detect_tilted: bool,
+    pub verify_eyes: bool,
+    pub verify_skin: bool,
+    pub min_skin_ratio: f64,
+    pub fast_detection: bool,
+    pub detect_max_dimension: i32,
+    pub face_selection: FaceSelection,
+}
+
+impl Default for DetectionParams {
+    fn default() -> Self {
+        Self {
+            min_neighbors: 3,
+            min_face_size: FaceSize::Pixels(500),
+            max_face_size: None,
+            detect_profile: false,
+            detect_tilted: false,
+            verify_eyes: false,
+            verify_skin: false,
+            min_skin_ratio: 0.3,
+            fast_detection: false,
+            detect_max_dimension: 1600,
+            face_selection: FaceSelection::All,
+        }
+    }
+}
+
+impl DetectionParams {
+    pub fn process_options(&self) -> ProcessOptions {
+        ProcessOptions {
+            detection: self.detection_options(),
+            selection: self.face_selection,
+            overrides_file: None,
+        }
+    }
+
+    fn detection_options(&self) -> DetectionOptions {
+        DetectionOptions {
+            min_neighbors: self.min_neighbors,
+            min_face_size: self.min_face_size,
+            max_face_size: self.max_face_size,
+            profile: self.detect_profile,
+            rotation_angles: if self.detect_tilted {
+                vec![15.0, 30.0]
+            } else {
+                Vec::new()
+            },
+            verify_eyes: self.verify_eyes,
+            min_skin_ratio: self.verify_skin.then_some(self.min_skin_ratio),
+            detect_max_dimension: self.fast_detection.then_some(self.detect_max_dimension),
+        }
+    }
+}
+
+/// Everything the GUI remembers between sessions. Stored as `key=value`
+/// lines; unknown keys and unparsable values fall back to the defaults so
+/// an old or hand-edited file never stops the app from starting.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub input_path: Option<PathBuf>,
+    pub output_path: Option<PathBuf>,
+    /// Most recently used first
+    pub recent_folders: Vec<PathBuf>,
+    pub window_size: [f32; 2],
+    pub params: DetectionParams,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            input_path: None,
+            output_path: None,
+            recent_folders: Vec::new(),
+            window_size: DEFAULT_WINDOW_SIZE,
+            params: DetectionParams::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings file, or the defaults if there is none.
+    pub fn load() -> Self {
+        config_dir()
+            .and_then(|dir| fs::read_to_string(dir.join(SETTINGS_FILE)).ok())
+            .map(|contents| Self::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let dir = config_dir().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no home or config folder found")
+        })?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(SETTINGS_FILE), self.to_string())
+    }
+
+    fn parse(contents: &str) -> Self {
+        let mut settings = Self::default();
+        let params = &mut settings.params;
+
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "input" => settings.input_path = Some(PathBuf::from(value)),
+                "output" => settings.output_path = Some(PathBuf::from(value)),
+                "recent" => settings.recent_folders.push(PathBuf::from(value)),
+                "window" => {
+                    if let Some((w, h)) = value.split_once('x') {
+                        if let (Ok(w), Ok(h)) = (w.parse(), h.parse()) {
+                            settings.window_size = [w, h];
+                        }
+                    }
+                }
+                "min_neighbors" => parse_into(value, &mut params.min_neighbors),
+                "min_face_size" => parse_into(value, &mut params.min_face_size),
+                "max_face_size" => params.max_face_size = value.parse().ok(),
+                "profile" => parse_into(value, &mut params.detect_profile),
+                "tilted" => parse_into(value, &mut params.detect_tilted),
+                "verify_eyes" => parse_into(value, &mut params.verify_eyes),
+                "verify_skin" => parse_into(value, &mut params.verify_skin),
+                "min_skin_ratio" => parse_into(value, &mut params.min_skin_ratio),
+                "fast_detection" => parse_into(value, &mut params.fast_detection),
+                "detect_max_dimension" => parse_into(value, &mut params.detect_max_dimension),
+                "select" => parse_into(value, &mut params.face_selection),
+                _ => {}
+            }
+        }
+
+        settings.recent_folders.truncate(MAX_RECENT_FOLDERS);
+        settings
+    }
+}
+
+impl std::fmt::Display for Settings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(path) = &self.input_path {
+            writeln!(f, "input={}", path.display())?;
+        }
+        if let Some(path) = &self.output_path {
+            writeln!(f, "output={}", path.display())?;
+        }
+        for path in &self.recent_folders {
+            writeln!(f, "recent={}", path.display())?;
+        }
+        writeln!(
+            f,
+            "window={}x{}",
+            self.window_size[0].round(),
+            self.window_size[1].round()
+        )?;
+
+        let params = &self.params;
+        writeln!(f, "min_neighbors={}", params.min_neighbors)?;
+        writeln!(f, "min_face_size={}", params.min_face_size)?;
+        if let Some(max_face_size) = params.max_face_size {
+            writeln!(f, "max_face_size={}", max_face_size)?;
+        }
+        writeln!(f, "profile={}", params.detect_profile)?;
+        writeln!(f, "tilted={}", params.detect_tilted)?;
+        writeln!(f, "verify_eyes={}", params.verify_eyes)?;
+        writeln!(f, "verify_skin={}", params.verify_skin)?;
+        writeln!(f, "min_skin_ratio={}", params.min_skin_ratio)?;
+        writeln!(f, "fast_detection={}", params.fast_detection)?;
+        writeln!(f, "detect_max_dimension={}", params.detect_max_dimension)?;
+        writeln!(f, "select={}", params.face_selection)
+    }
+}
+
+/// Moves `path` to the front of a recent-folders list.
+pub fn remember_folder(recent_folders: &mut Vec<PathBuf>, path: &Path) {
+    recent_folders.retain(|p| p != path);
+    recent_folders.insert(0, path.to_path_buf());
+    recent_folders.truncate(MAX_RECENT_FOLDERS);
+}
+
+/// Overwrites `target` only if `value` parses.
+fn parse_into<T: std::str::FromStr>(value: &str, target: &mut T) {
+    if let Ok(parsed) = value.parse() {
+        *target = parsed;
+    }
+}
+
+/// Per-user config folder: `%APPDATA%\headshot` on Windows,
+/// `$XDG_CONFIG_HOME/headshot` or `~/.config/headshot` elsewhere.
+fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+    };
+    base.map(|dir| dir.join("headshot"))
+}
@@ -0,0 +1,353 @@
+use crate::manifest;
+use crate::processor;
+use crate::thumbnails::ThumbnailLoader;
+use eframe::egui;
+use egui_extras::{Column, TableBuilder};
+use lru::LruCache;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::Duration;
+
+/// Number of source thumbnail textures kept alive
+const THUMB_CACHE_SIZE: usize = 512;
+
+const NO_FACES_COLOR: egui::Color32 = egui::Color32::from_rgb(225, 45, 0);
+const FACES_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 140, 60);
+const UNKNOWN_COLOR: egui::Color32 = egui::Color32::from_rgb(110, 110, 110);
+
+/// How long the folders must stay the same before they are scanned, so
+/// typing a path does not start a scan per keystroke
+const SCAN_DELAY: Duration = Duration::from_millis(300);
+
+/// One image from the input folders.
+struct SourceEntry {
+    path: PathBuf,
+    /// Resolved path, as recorded in the manifest and used for face counts
+    canonical: PathBuf,
+}
+
+/// Result of scanning the folders on a background thread.
+struct FolderScan {
+    generation: u64,
+    /// The input images, if the inputs changed
+    sources: Option<Vec<SourceEntry>>,
+    face_counts: HashMap<PathBuf, usize>,
+}
+
+/// Thumbnails of the input images with the number of faces found in each,
+/// so images where detection failed stand out.
+pub struct SourceBrowser {
+    inputs: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    sources: Vec<SourceEntry>,
+    /// Crops per source from the output manifest, and 0 for sources the
+    /// run record lists without crops; sources never processed are missing
+    face_counts: HashMap<PathBuf, usize>,
+    /// Faces per source recorded by the current run, which take precedence
+    run_counts: HashMap<PathBuf, usize>,
+    /// Incremented per scan, so results of superseded scans are dropped
+    scan_generation: Arc<AtomicU64>,
+    scan_sender: Sender<FolderScan>,
+    scan_receiver: Receiver<FolderScan>,
+    scanning: bool,
+    only_without_faces: bool,
+    thumbnails: ThumbnailLoader,
+    thumb_cache: LruCache<PathBuf, egui::TextureHandle>,
+    thumb_failed: HashSet<PathBuf>,
+    /// Set when a source with crops is clicked
+    selected_source: Option<PathBuf>,
+    status: Option<String>,
+}
+
+impl SourceBrowser {
+    pub fn new() -> Self {
+        let (scan_sender, scan_receiver) = channel();
+        Self {
+            inputs: Vec::new(),
+            output: None,
+            sources: Vec::new(),
+            face_counts: HashMap::new(),
+            run_counts: HashMap::new(),
+            scan_generation: Arc::new(AtomicU64::new(0)),
+            scan_sender,
+            scan_receiver,
+            scanning: false,
+            only_without_faces: false,
+            thumbnails: ThumbnailLoader::new(),
+            thumb_cache: LruCache::new(NonZeroUsize::new(THUMB_CACHE_SIZE).unwrap()),
+            thumb_failed: HashSet::new(),
+            selected_source: None,
+            status: None,
+        }
+    }
+
+    /// Starts listing the images in `inputs` and reading the face counts
+    /// recorded in the manifest of `output` in the background. Does nothing
+    /// if neither has changed.
+    pub fn set_folders(&mut self, inputs: &[PathBuf], output: Option<&Path>) {
+        if self.inputs == inputs && self.output.as_deref() == output {
+            return;
+        }
+
+        let inputs_changed = self.inputs != inputs;
+        self.inputs = inputs.to_vec();
+        self.output = output.map(Path::to_path_buf);
+        self.run_counts.clear();
+        self.status = None;
+        self.scanning = true;
+
+        let generation = self.scan_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let current = self.scan_generation.clone();
+        let tx = self.scan_sender.clone();
+        let inputs = inputs.to_vec();
+        let output = self.output.clone();
+        thread::spawn(move || {
+            thread::sleep(SCAN_DELAY);
+            if current.load(Ordering::SeqCst) != generation {
+                return; // The folders changed again while waiting
+            }
+
+            let sources = inputs_changed.then(|| {
+                let mut files = processor::collect_image_files_from(&inputs).unwrap_or_default();
+                files.sort();
+                files
+                    .into_iter()
+                    .map(|path| SourceEntry {
+                        canonical: fs::canonicalize(&path).unwrap_or_else(|_| path.clone()),
+                        path,
+                    })
+                    .collect()
+            });
+            // Processed sources without crops are only in the run record
+            let face_counts = output
+                .map(|output| {
+                    let mut counts = manifest::face_counts(&output);
+                    for source in manifest::load_runs(&output).into_keys() {
+                        counts.entry(source).or_insert(0);
+                    }
+                    counts
+                })
+                .unwrap_or_default();
+            tx.send(FolderScan {
+                generation,
+                sources,
+                face_counts,
+            })
+            .unwrap_or_default();
+        });
+    }
+
+    /// Records the number of faces a run found in `source`.
+    pub fn record(&mut self, source: &Path, face_count: usize) {
+        let canonical = fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf());
+        self.run_counts.insert(canonical, face_count);
+    }
+
+    /// Faces found in `source`, if known.
+    fn face_count(&self, source: &SourceEntry) -> Option<usize> {
+        self.run_counts
+            .get(&source.canonical)
+            .or_else(|| self.face_counts.get(&source.canonical))
+            .copied()
+    }
+
+    /// Returns the source whose crops should be shown if one was clicked
+    /// since the last call.
+    pub fn take_selected_source(&mut self) -> Option<PathBuf> {
+        self.selected_source.take()
+    }
+
+    fn update(&mut self, ctx: &egui::Context) {
+        let generation = self.scan_generation.load(Ordering::SeqCst);
+        while let Ok(scan) = self.scan_receiver.try_recv() {
+            if scan.generation != generation {
+                continue;
+            }
+            if let Some(sources) = scan.sources {
+                self.sources = sources;
+                self.thumbnails.cancel_pending();
+                self.thumb_failed.clear();
+            }
+            self.face_counts = scan.face_counts;
+            self.scanning = false;
+        }
+        if self.scanning {
+            ctx.request_repaint_after(SCAN_DELAY);
+        }
+
+        let mut any_received = false;
+        while let Some((path, thumbnail)) = self.thumbnails.try_recv() {
+            match thumbnail {
+                Some(thumbnail) => {
+                    let tex_name = format!("source_thumb_{}", path.to_string_lossy());
+                    let texture =
+                        ctx.load_texture(tex_name, thumbnail.image, egui::TextureOptions::LINEAR);
+                    self.thumb_cache.put(path, texture);
+                }
+                None => {
+                    self.thumb_failed.insert(path);
+                }
+            }
+            any_received = true;
+        }
+        if any_received {
+            ctx.request_repaint();
+        }
+    }
+
+    /// Shows the browser window. Returns `false` once the user closes it.
+    pub fn show(&mut self, ctx: &egui::Context) -> bool {
+        self.update(ctx);
+        let mut browser_open = true;
+
+        egui::Window::new("Source Images")
+            .resizable(true)
+            .default_size([800.0, 600.0])
+            .open(&mut browser_open)
+            .show(ctx, |ui| {
+                if self.sources.is_empty() {
+                    ui.centered_and_justified(|ui| {
+                        if self.scanning {
+                            ui.spinner();
+                        } else {
+                            ui.label("Select an input folder or drop images to browse them");
+                        }
+                    });
+                    return;
+                }
+
+                let counts: Vec<Option<usize>> =
+                    self.sources.iter().map(|s| self.face_count(s)).collect();
+                let without_faces = counts.iter().filter(|&&c| c == Some(0)).count();
+                let unprocessed = counts.iter().filter(|c| c.is_none()).count();
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} images", self.sources.len()));
+                    ui.separator();
+                    ui.colored_label(NO_FACES_COLOR, format!("{} without faces", without_faces));
+                    if unprocessed > 0 {
+                        ui.separator();
+                        ui.label(format!("{} without crops or not processed yet", unprocessed));
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.only_without_faces, "Only images without faces");
+                });
+                if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+                ui.separator();
+
+                let shown: Vec<usize> = (0..self.sources.len())
+                    .filter(|&i| !self.only_without_faces || counts[i] == Some(0))
+                    .collect();
+                let mut clicked = None;
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let thumb_size = 140.0;
+                    let cols = ((ui.available_width() / thumb_size).floor() as usize).max(1);
+                    let rows = shown.len().div_ceil(cols);
+
+                    TableBuilder::new(ui)
+                        .columns(Column::exact(thumb_size), cols)
+                        .body(|body| {
+                            body.rows(thumb_size, rows, |mut row| {
+                                let row_index = row.index();
+                                for col in 0..cols {
+                                    let Some(&index) = shown.get(row_index * cols + col) else {
+                                        continue;
+                                    };
+                                    let source = &self.sources[index];
+                                    let faces = counts[index];
+                                    let thumb = self.thumb_cache.get(&source.path).cloned();
+                                    let failed = self.thumb_failed.contains(&source.path);
+                                    if thumb.is_none() && !failed {
+                                        self.thumbnails.request(&source.path);
+                                    }
+
+                                    row.col(|ui| {
+                                        let response = match &thumb {
+                                            Some(tex) => ui.add_sized(
+                                                [120.0, 120.0],
+                                                egui::ImageButton::new(tex),
+                                            ),
+                                            None if failed => ui.add_sized(
+                                                [120.0, 120.0],
+                                                egui::Button::new("⚠ Unreadable"),
+                                            ),
+                                            None => {
+                                                ui.add_sized([120.0, 120.0], egui::Spinner::new())
+                                            }
+                                        };
+                                        paint_badge(ui, response.rect, faces);
+
+                                        let response = response.on_hover_text(format!(
+                                            "{}\n{}",
+                                            source.path.display(),
+                                            match faces {
+                                                Some(0) => "No crops".to_string(),
+                                                Some(1) => "1 face".to_string(),
+                                                Some(n) => format!("{} faces", n),
+                                                None => "Not processed yet".to_string(),
+                                            }
+                                        ));
+                                        if response.clicked() {
+                                            clicked = Some(index);
+                                        }
+                                    });
+                                }
+                            });
+                        });
+                });
+
+                if let Some(index) = clicked {
+                    let source = &self.sources[index];
+                    let name = source.path.file_name().unwrap_or_default().to_string_lossy();
+                    match counts[index] {
+                        Some(0) => {
+                            self.status = Some(format!(
+                                "No crops from {}; try it in the Detection Preview with looser parameters",
+                                name
+                            ));
+                        }
+                        None => {
+                            self.status = Some(format!(
+                                "{} has not been processed into the output folder yet",
+                                name
+                            ));
+                        }
+                        Some(_) => {
+                            self.status = None;
+                            self.selected_source = Some(source.canonical.clone());
+                        }
+                    }
+                }
+            });
+
+        browser_open
+    }
+}
+
+/// Draws the face count in the top-right corner of a thumbnail: red for
+/// none, grey with `?` when unknown.
+fn paint_badge(ui: &egui::Ui, rect: egui::Rect, faces: Option<usize>) {
+    let (text, color) = match faces {
+        Some(0) => ("0".to_string(), NO_FACES_COLOR),
+        Some(n) => (n.to_string(), FACES_COLOR),
+        None => ("?".to_string(), UNKNOWN_COLOR),
+    };
+    let center = rect.right_top() + egui::vec2(-14.0, 14.0);
+    let painter = ui.painter();
+    painter.circle_filled(center, 11.0, color);
+    painter.text(
+        center,
+        egui::Align2::CENTER_CENTER,
+        text,
+        egui::FontId::proportional(13.0),
+        egui::Color32::WHITE,
+    );
+}
@@ -0,0 +1,204 @@
+use crossbeam::channel::{Receiver, Sender, unbounded};
+use eframe::egui;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Longest side of a generated thumbnail, in pixels
+pub const THUMBNAIL_SIZE: u32 = 128;
+
+/// Upper bound on thumbnail worker threads
+const MAX_WORKERS: usize = 4;
+
+/// Cached thumbnails not used for this long are removed at startup
+const CACHE_MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 30);
+
+/// Most thumbnails kept in the disk cache; the least recently used go first
+const CACHE_MAX_ENTRIES: usize = 20_000;
+
+/// A decoded thumbnail and what is known about the image it came from.
+pub struct Thumbnail {
+    pub image: egui::ColorImage,
+    /// Full size of the source image
+    pub dimensions: [u32; 2],
+    /// Modification time of the source image the thumbnail was made from
+    pub modified: SystemTime,
+}
+
+/// A finished request: the thumbnail, or `None` if the image could not be
+/// read
+pub type ThumbnailLoad = (PathBuf, Option<Thumbnail>);
+
+/// Generates thumbnails on demand on a small pool of worker threads. Each
+/// thumbnail is also written to a per-user disk cache keyed by the image's
+/// path and modification time, so reopening a folder only decodes images
+/// that are new or have changed.
+pub struct ThumbnailLoader {
+    job_sender: Sender<PathBuf>,
+    /// Kept to drop queued work when the gallery switches folders
+    job_receiver: Receiver<PathBuf>,
+    result_receiver: Receiver<ThumbnailLoad>,
+    pending: HashSet<PathBuf>,
+}
+
+impl ThumbnailLoader {
+    pub fn new() -> Self {
+        let (job_sender, job_receiver) = unbounded::<PathBuf>();
+        let (result_sender, result_receiver) = unbounded();
+        let cache_dir = cache_dir().filter(|dir| fs::create_dir_all(dir).is_ok());
+        if let Some(dir) = cache_dir.clone() {
+            thread::spawn(move || prune_cache(&dir));
+        }
+
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(2)
+            .clamp(1, MAX_WORKERS);
+        for _ in 0..workers {
+            let jobs = job_receiver.clone();
+            let results = result_sender.clone();
+            let cache_dir = cache_dir.clone();
+            thread::spawn(move || {
+                for path in jobs {
+                    let thumbnail = load_thumbnail(&path, cache_dir.as_deref());
+                    if results.send((path, thumbnail)).is_err() {
+                        break; // Loader dropped
+                    }
+                }
+            });
+        }
+
+        Self {
+            job_sender,
+            job_receiver,
+            result_receiver,
+            pending: HashSet::new(),
+        }
+    }
+
+    /// Queues a thumbnail for `path` unless one is already on its way.
+    pub fn request(&mut self, path: &Path) {
+        if self.pending.insert(path.to_path_buf()) {
+            self.job_sender.send(path.to_path_buf()).unwrap_or_default();
+        }
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Drops queued requests that no worker has started yet.
+    pub fn cancel_pending(&mut self) {
+        while self.job_receiver.try_recv().is_ok() {}
+        self.pending.clear();
+    }
+
+    pub fn try_recv(&mut self) -> Option<ThumbnailLoad> {
+        let (path, thumbnail) = self.result_receiver.try_recv().ok()?;
+        self.pending.remove(&path);
+        Some((path, thumbnail))
+    }
+}
+
+/// Reads the thumbnail for `path` from the disk cache, or decodes the image
+/// and adds it to the cache.
+fn load_thumbnail(path: &Path, cache_dir: Option<&Path>) -> Option<Thumbnail> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let cache_file = cache_dir.map(|dir| dir.join(cache_key(path, modified)));
+
+    if let Some((file, cached)) = cache_file
+        .as_ref()
+        .and_then(|file| image::open(file).ok().map(|cached| (file, cached)))
+    {
+        if let Ok((width, height)) = image::image_dimensions(path) {
+            // Marks the entry as recently used for pruning
+            let _ = fs::File::options()
+                .append(true)
+                .open(file)
+                .and_then(|f| f.set_modified(SystemTime::now()));
+            return Some(Thumbnail {
+                image: color_image(&cached),
+                dimensions: [width, height],
+                modified,
+            });
+        }
+    }
+
+    let img = image::open(path).ok()?;
+    let thumb = img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    if let Some(file) = &cache_file {
+        // A failed write only costs a decode next time
+        let _ = thumb.save_with_format(file, image::ImageFormat::Png);
+    }
+
+    Some(Thumbnail {
+        image: color_image(&thumb),
+        dimensions: [img.width(), img.height()],
+        modified,
+    })
+}
+
+fn color_image(img: &image::DynamicImage) -> egui::ColorImage {
+    let rgba = img.to_rgba8();
+    egui::ColorImage::from_rgba_unmultiplied(
+        [rgba.width() as usize, rgba.height() as usize],
+        rgba.as_flat_samples().as_slice(),
+    )
+}
+
+/// Cache file name for an image; a changed file gets a new name, so stale
+/// entries are never read. The path hash is CRC-32 so names stay the same
+/// across builds.
+fn cache_key(path: &Path, modified: SystemTime) -> String {
+    let path_hash = crc32fast::hash(path.as_os_str().as_encoded_bytes());
+    let modified = modified
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{:08x}-{:x}.png", path_hash, modified)
+}
+
+/// Removes cached thumbnails unused for [`CACHE_MAX_AGE`], then the least
+/// recently used ones beyond [`CACHE_MAX_ENTRIES`]. Entries of changed or
+/// deleted images are never read again and age out this way.
+fn prune_cache(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let now = SystemTime::now();
+    let mut kept: Vec<(SystemTime, PathBuf)> = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(used) = entry.metadata().and_then(|m| m.modified()) else {
+            continue;
+        };
+        if now.duration_since(used).unwrap_or_default() > CACHE_MAX_AGE {
+            let _ = fs::remove_file(&path);
+        } else {
+            kept.push((used, path));
+        }
+    }
+
+    if kept.len() > CACHE_MAX_ENTRIES {
+        kept.sort_by(|a, b| b.0.cmp(&a.0));
+        for (_, path) in kept.drain(CACHE_MAX_ENTRIES..) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Per-user cache folder: `%LOCALAPPDATA%\headshot\thumbnails` on Windows,
+/// `$XDG_CACHE_HOME/headshot/thumbnails` or `~/.cache/headshot/thumbnails`
+/// elsewhere.
+fn cache_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+    };
+    base.map(|dir| dir.join("headshot").join("thumbnails"))
+}
@@ -0,0 +1,122 @@
+use eframe::egui;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often watched folders are rescanned
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A file that appeared, changed or disappeared since the previous scan.
+#[derive(Clone, Debug)]
+pub enum FolderChange {
+    Added(PathBuf, Option<SystemTime>),
+    Modified(PathBuf, Option<SystemTime>),
+    Removed(PathBuf),
+}
+
+/// Watches the files directly inside a few folders by rescanning them on a
+/// background thread, which works the same on every platform and network
+/// share. Stops when dropped.
+pub struct FolderWatcher {
+    folders: Vec<PathBuf>,
+    receiver: Receiver<Vec<FolderChange>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl FolderWatcher {
+    /// Starts watching `folders`. `known` holds the files the caller has
+    /// already listed with their modification times; the first scan reports
+    /// how the folders differ from it, so nothing created in between is
+    /// missed. `ctx` is repainted whenever changes are found.
+    pub fn new(
+        folders: Vec<PathBuf>,
+        known: HashMap<PathBuf, Option<SystemTime>>,
+        ctx: &egui::Context,
+    ) -> Self {
+        let (sender, receiver) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let watched = folders.clone();
+        let stopped = stop.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let mut known = known;
+            while !stopped.load(Ordering::Relaxed) {
+                let current = scan(&watched);
+                let changes = diff(&known, &current);
+                known = current;
+                if !changes.is_empty() {
+                    if sender.send(changes).is_err() {
+                        break; // Watcher dropped
+                    }
+                    ctx.request_repaint();
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+
+        Self {
+            folders,
+            receiver,
+            stop,
+        }
+    }
+
+    pub fn folders(&self) -> &[PathBuf] {
+        &self.folders
+    }
+
+    /// Changes found since the last call, oldest first.
+    pub fn changes(&self) -> Vec<FolderChange> {
+        self.receiver.try_iter().flatten().collect()
+    }
+}
+
+impl Drop for FolderWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Files directly inside `folders` with their modification times.
+fn scan(folders: &[PathBuf]) -> HashMap<PathBuf, Option<SystemTime>> {
+    folders
+        .iter()
+        .filter_map(|folder| fs::read_dir(folder).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata
+                .is_file()
+                .then(|| (entry.path(), metadata.modified().ok()))
+        })
+        .collect()
+}
+
+fn diff(
+    before: &HashMap<PathBuf, Option<SystemTime>>,
+    after: &HashMap<PathBuf, Option<SystemTime>>,
+) -> Vec<FolderChange> {
+    let mut changes: Vec<FolderChange> = after
+        .iter()
+        .filter_map(|(path, &modified)| match before.get(path) {
+            None => Some(FolderChange::Added(path.clone(), modified)),
+            Some(&previous) if previous != modified => {
+                Some(FolderChange::Modified(path.clone(), modified))
+            }
+            Some(_) => None,
+        })
+        .collect();
+    changes.extend(
+        before
+            .keys()
+            .filter(|path| !after.contains_key(*path))
+            .map(|path| FolderChange::Removed(path.clone())),
+    );
+    changes
+}
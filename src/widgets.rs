@@ -0,0 +1,73 @@
+use crate::detector::FaceSize;
+use crate::processor::FaceSelection;
+use eframe::egui;
+
+/// Whether keyboard shortcuts belong to the window titled `title`: the
+/// pointer is over it, or it is the front window and the pointer is not
+/// over another one.
+pub fn window_has_keyboard(ctx: &egui::Context, title: &str) -> bool {
+    let layer = egui::LayerId::new(egui::Order::Middle, egui::Id::new(title));
+    let hovered = ctx
+        .input(|i| i.pointer.hover_pos())
+        .and_then(|pos| ctx.layer_id_at(pos))
+        .filter(|hovered| hovered.order == egui::Order::Middle);
+    hovered.or_else(|| ctx.top_layer_id()) == Some(layer)
+}
+
+/// Slider for a face size limit, with a toggle between absolute pixels and a
+/// percentage of the image's short side.
+pub fn face_size_editor(ui: &mut egui::Ui, label: &str, size: &mut FaceSize) {
+    ui.horizontal(|ui| {
+        match size {
+            FaceSize::Pixels(px) => {
+                ui.add(egui::Slider::new(px, 10..=2000).suffix(" px").text(label));
+            }
+            FaceSize::Percent(pct) => {
+                ui.add(egui::Slider::new(pct, 1.0..=100.0).suffix("%").text(label));
+            }
+        }
+
+        let mut relative = matches!(size, FaceSize::Percent(_));
+        if ui.checkbox(&mut relative, "% of short side").changed() {
+            *size = if relative {
+                FaceSize::Percent(10.0)
+            } else {
+                FaceSize::Pixels(500)
+            };
+        }
+    });
+}
+
+/// Combo box for the per-image face selection policy, plus the value the
+/// chosen policy needs.
+pub fn face_selection_editor(ui: &mut egui::Ui, selection: &mut FaceSelection) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_label("Faces Per Image")
+            .selected_text(selection.label())
+            .show_ui(ui, |ui| {
+                for option in [
+                    FaceSelection::All,
+                    FaceSelection::Largest,
+                    FaceSelection::MostCentral,
+                    FaceSelection::TopN(2),
+                    FaceSelection::RelativeToLargest(50.0),
+                ] {
+                    let selected =
+                        std::mem::discriminant(selection) == std::mem::discriminant(&option);
+                    if ui.selectable_label(selected, option.label()).clicked() && !selected {
+                        *selection = option;
+                    }
+                }
+            });
+
+        match selection {
+            FaceSelection::TopN(n) => {
+                ui.add(egui::DragValue::new(n).clamp_range(1..=20).prefix("N = "));
+            }
+            FaceSelection::RelativeToLargest(pct) => {
+                ui.add(egui::Slider::new(pct, 1.0..=100.0).suffix("% of largest"));
+            }
+            _ => {}
+        }
+    });
+}